@@ -0,0 +1,37 @@
+//! A small CLI for encrypting values to put in configuration files.
+//!
+//! ```sh
+//! # Make a new key
+//! cargo run --example encrypt --features encryption -- generate ./cogwheel.key
+//! # Encrypt a value with a key file, or with the key in `COGWHEEL_KEY` if no file is given
+//! cargo run --example encrypt --features encryption -- "hunter2" ./cogwheel.key
+//! ```
+//!
+//! The output (`enc:v1:...`) can be pasted into a configuration file
+//! and is decrypted by `ConfigurationBuilder::decrypt_with`.
+
+use std::{env, fs::File, io::Write};
+
+use anyhow::{bail, Result};
+use cogwheel::encryption::Key;
+
+fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<&str>>()
+        .as_slice()
+    {
+        ["generate", path] => {
+            File::create_new(path)?.write_all(Key::generate().to_base64().as_bytes())?;
+            println!("Wrote a new key to {path}, keep it out of version control!");
+        }
+        [value] => println!("{}", Key::from_env("COGWHEEL_KEY")?.encrypt(value)?),
+        [value, path] => println!("{}", Key::from_file(path)?.encrypt(value)?),
+        _ => bail!("usage: encrypt generate <key file> | encrypt <value> [key file]"),
+    }
+
+    Ok(())
+}
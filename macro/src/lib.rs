@@ -1,25 +1,235 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, Data, DeriveInput, Field, Fields, Ident, Type};
+use syn::{parse_macro_input, Data, DeriveInput, Expr, Field, Fields, Ident, LitStr, Type};
 
-#[proc_macro_derive(Configuration)]
+#[proc_macro_derive(Configuration, attributes(cogwheel))]
 #[allow(clippy::missing_panics_doc)]
 pub fn configuration_derive(input: TokenStream) -> TokenStream {
     let ast: DeriveInput = parse_macro_input!(input as DeriveInput);
-    impl_configuration(&ast)
+    impl_configuration(&ast).unwrap_or_else(|error: syn::Error| error.to_compile_error().into())
 }
 
-fn impl_configuration(ast: &syn::DeriveInput) -> TokenStream {
+fn impl_configuration(ast: &syn::DeriveInput) -> syn::Result<TokenStream> {
     let name: &Ident = &ast.ident;
+    let fields: Vec<FieldOptions> = parse_fields(ast)?;
+    let defaults_fn: proc_macro2::TokenStream = gen_defaults_fn(&fields);
+    let aliases_fn: proc_macro2::TokenStream = gen_aliases_fn(&fields);
+
     let gen: proc_macro2::TokenStream = quote! {
-        impl Configuration for #name {}
+        impl Configuration for #name {
+            #defaults_fn
+            #aliases_fn
+        }
+    };
+    Ok(gen.into())
+}
+
+/// Generates `Configuration::defaults` from `#[cogwheel(default = "...")]`, `#[cogwheel(default_fn = ...)]`
+/// and `#[cogwheel(nested)]`, or nothing if no field has them.
+fn gen_defaults_fn(fields: &[FieldOptions]) -> proc_macro2::TokenStream {
+    let defaults: Vec<proc_macro2::TokenStream> = fields
+        .iter()
+        .filter_map(|field: &FieldOptions| {
+            let key: &str = &field.key;
+            let ty: &Type = &field.ty;
+            if let Some(default) = &field.default {
+                Some(quote! {
+                    defaults.insert(
+                        #key.to_string(),
+                        cogwheel::secret::exposed(|| {
+                            cogwheel::document::to_document(&{
+                                let value: #ty = #default;
+                                value
+                            })
+                        })?,
+                    );
+                })
+            } else if field.nested {
+                Some(quote! {
+                    let nested: cogwheel::document::Document =
+                        <#ty as cogwheel::Configuration>::defaults()?;
+                    if nested.as_object().map_or(true, |nested| !nested.is_empty()) {
+                        defaults.insert(#key.to_string(), nested);
+                    }
+                })
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    if defaults.is_empty() {
+        return quote! {};
+    }
+
+    quote! {
+        fn defaults() -> Result<cogwheel::document::Document, cogwheel::Error> {
+            let mut defaults: cogwheel::document::Map<String, cogwheel::document::Document> =
+                cogwheel::document::Map::new();
+            #(#defaults)*
+            Ok(cogwheel::document::Document::Object(defaults))
+        }
+    }
+}
+
+/// Generates `Configuration::aliases` from `#[cogwheel(alias = "...")]`, `#[cogwheel(deprecated = "...")]`
+/// and `#[cogwheel(nested)]`, or nothing if no field has them.
+fn gen_aliases_fn(fields: &[FieldOptions]) -> proc_macro2::TokenStream {
+    let aliases: Vec<proc_macro2::TokenStream> = fields
+        .iter()
+        .filter(|field: &&FieldOptions| {
+            !field.aliases.is_empty() || field.deprecated.is_some() || field.nested
+        })
+        .map(|field: &FieldOptions| {
+            let key: &str = &field.key;
+            let ty: &Type = &field.ty;
+            let deprecated: proc_macro2::TokenStream = field.deprecated.as_ref().map_or_else(
+                || quote! { None },
+                |deprecated: &String| quote! { Some(#deprecated.to_string()) },
+            );
+
+            let mut aliases: Vec<proc_macro2::TokenStream> = field
+                .aliases
+                .iter()
+                .map(|alias: &String| quote! { Some(#alias.to_string()) })
+                .collect();
+            if aliases.is_empty() && field.deprecated.is_some() {
+                aliases.push(quote! { None });
+            }
+            let nested: proc_macro2::TokenStream = if field.nested {
+                quote! {
+                    aliases.extend(
+                        <#ty as cogwheel::Configuration>::aliases()
+                            .into_iter()
+                            .map(|alias: cogwheel::warning::Alias| alias.nested_in(#key)),
+                    );
+                }
+            } else {
+                quote! {}
+            };
+
+            quote! {
+                #(
+                    aliases.push(cogwheel::warning::Alias {
+                        key: #key.to_string(),
+                        alias: #aliases,
+                        deprecated: #deprecated,
+                    });
+                )*
+                #nested
+            }
+        })
+        .collect();
+
+    if aliases.is_empty() {
+        return quote! {};
+    }
+
+    quote! {
+        fn aliases() -> Vec<cogwheel::warning::Alias> {
+            let mut aliases: Vec<cogwheel::warning::Alias> = Vec::new();
+            #(#aliases)*
+            aliases
+        }
+    }
+}
+
+/// Everything `#[derive(Configuration)]` needs to know about a field.
+struct FieldOptions {
+    /// The name of the field in a file, which respects `#[serde(rename = "...")]`.
+    key: String,
+    ty: Type,
+    /// From `#[cogwheel(default = "...")]` or `#[cogwheel(default_fn = ...)]`.
+    default: Option<Expr>,
+    /// From `#[cogwheel(alias = "...")]`, which can be used more than once.
+    aliases: Vec<String>,
+    /// From `#[cogwheel(deprecated = "...")]`.
+    deprecated: Option<String>,
+    /// From `#[cogwheel(nested)]`, for fields that are also a `Configuration`.
+    nested: bool,
+}
+
+/// Reads the `#[cogwheel(...)]` and `#[serde(rename = "...")]` attributes of every named field.
+/// Anything that isn't a struct with named fields has no options.
+fn parse_fields(ast: &DeriveInput) -> syn::Result<Vec<FieldOptions>> {
+    let Data::Struct(data_struct) = &ast.data else {
+        return Ok(Vec::new());
     };
-    gen.into()
+    let Fields::Named(fields_named) = &data_struct.fields else {
+        return Ok(Vec::new());
+    };
+
+    fields_named
+        .named
+        .iter()
+        .map(|field: &Field| {
+            let mut options: FieldOptions = FieldOptions {
+                key: field
+                    .ident
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+                ty: field.ty.clone(),
+                default: None,
+                aliases: Vec::new(),
+                deprecated: None,
+                nested: false,
+            };
+
+            for attr in &field.attrs {
+                if attr.path().is_ident("serde") {
+                    // Everything but `rename = "..."` is skipped, serde checks the rest.
+                    attr.parse_nested_meta(|meta| {
+                        if meta.input.peek(syn::Token![=]) {
+                            let value: Expr = meta.value()?.parse()?;
+                            if let (
+                                true,
+                                Expr::Lit(syn::ExprLit {
+                                    lit: syn::Lit::Str(key),
+                                    ..
+                                }),
+                            ) = (meta.path.is_ident("rename"), value)
+                            {
+                                options.key = key.value();
+                            }
+                        } else if meta.input.peek(syn::token::Paren) {
+                            let content;
+                            syn::parenthesized!(content in meta.input);
+                            content.parse::<proc_macro2::TokenStream>()?;
+                        }
+                        Ok(())
+                    })?;
+                } else if attr.path().is_ident("cogwheel") {
+                    attr.parse_nested_meta(|meta| {
+                        if meta.path.is_ident("default") {
+                            options.default = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                        } else if meta.path.is_ident("default_fn") {
+                            let path: syn::Path = meta.value()?.parse()?;
+                            options.default = Some(syn::parse_quote!(#path()));
+                        } else if meta.path.is_ident("alias") {
+                            options
+                                .aliases
+                                .push(meta.value()?.parse::<LitStr>()?.value());
+                        } else if meta.path.is_ident("deprecated") {
+                            options.deprecated = Some(meta.value()?.parse::<LitStr>()?.value());
+                        } else if meta.path.is_ident("nested") {
+                            options.nested = true;
+                        } else {
+                            return Err(meta.error("unknown cogwheel attribute"));
+                        }
+                        Ok(())
+                    })?;
+                }
+            }
+
+            Ok(options)
+        })
+        .collect()
 }
 
 /// A macro which generates a struct from `T`, let's call it `TSparse`,
 /// where all keys of `T` (recursive) are `Option`.
-/// 
+///
 /// Anything using `replace*` on a builder **must** have `#[with_sparse]`.
 #[proc_macro_attribute]
 pub fn with_sparse(_attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -27,18 +237,23 @@ pub fn with_sparse(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let struct_name: &Ident = &input.ident;
 
     // Generate the new struct name by appending "Sparse"
-    let sparse_struct_name: Ident = syn::Ident::new(&format!("{}Sparse", struct_name), struct_name.span());
+    let sparse_struct_name: Ident =
+        syn::Ident::new(&format!("{}Sparse", struct_name), struct_name.span());
 
     // Collect field information and wrap in `Option`
     let fields: Vec<proc_macro2::TokenStream> = if let Data::Struct(data_struct) = &input.data {
         if let Fields::Named(fields_named) = &data_struct.fields {
-            fields_named.named.iter().map(|f: &Field| {
-                let field_name: &Option<Ident> = &f.ident;
-                let field_ty: &Type = &f.ty;
-                quote! {
-                    #field_name: Option<#field_ty>
-                }
-            }).collect::<Vec<_>>()
+            fields_named
+                .named
+                .iter()
+                .map(|f: &Field| {
+                    let field_name: &Option<Ident> = &f.ident;
+                    let field_ty: &Type = &f.ty;
+                    quote! {
+                        #field_name: Option<#field_ty>
+                    }
+                })
+                .collect::<Vec<_>>()
         } else {
             panic!("#[sparse] can only be applied to structs with named fields.");
         }
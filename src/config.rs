@@ -1,16 +1,35 @@
 use std::{
     any::Any,
-    collections::HashMap,
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    env,
     ffi::OsStr,
-    fs::File,
-    io::{Read, Write},
-    path::Path,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
 };
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
-use crate::{Error, Sparse};
+#[cfg(feature = "encryption")]
+use crate::encryption::Key;
+use crate::{
+    document::{self, Document},
+    format::{Format, FormatChoice},
+    migration::Migrations,
+    shared::SharedFile,
+    source::{self, Source},
+    warning::{Alias, Warning, WarningSink},
+    Error, Sparse,
+};
+
+/// The path that `use_file` reads from stdin instead of a file.
+pub const STDIN_PATH: &str = "-";
+
+/// The name of the file that `use_instances` merges every other file over.
+pub const INSTANCE_DEFAULTS: &str = "_defaults";
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// The representation of a configuration file type.
@@ -18,10 +37,67 @@ use crate::{Error, Sparse};
 pub enum ConfigurationVariant {
     #[cfg(feature = "json")]
     Json,
+    /// JSON5, which also reads JSONC (JSON with comments).
+    /// Files are written as plain JSON, which is valid JSON5.
+    #[cfg(feature = "json5")]
+    Json5,
     #[cfg(feature = "toml")]
     Toml,
     #[cfg(feature = "yaml")]
     Yaml,
+    #[cfg(feature = "ron")]
+    Ron,
+    /// `.env` files, see `cogwheel::dotenv` for how they map onto `T`.
+    #[cfg(feature = "dotenv")]
+    Dotenv,
+    /// Java `.properties` files, see `cogwheel::properties` for how they map onto `T`.
+    #[cfg(feature = "properties")]
+    Properties,
+    /// HCL files, where blocks are tables and labelled blocks are tables of them.
+    #[cfg(feature = "hcl")]
+    Hcl,
+    /// KDL files, see `cogwheel::kdl` for how they map onto `T`.
+    #[cfg(feature = "kdl")]
+    Kdl,
+    /// XML files, see `cogwheel::xml` for how they map onto `T`.
+    #[cfg(feature = "xml")]
+    Xml,
+    /// `.msgpack` files, which are binary, so they're read with `use_bytes` or `use_file` and not `use_str`.
+    #[cfg(feature = "msgpack")]
+    Msgpack,
+    /// CBOR files, which are binary, so they're read with `use_bytes` or `use_file` and not `use_str`.
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+impl ConfigurationVariant {
+    /// Every variant enabled by features, in the order they're guessed in.
+    pub(crate) const ALL: &'static [Self] = &[
+        #[cfg(feature = "json")]
+        Self::Json,
+        #[cfg(feature = "json5")]
+        Self::Json5,
+        #[cfg(feature = "dotenv")]
+        Self::Dotenv,
+        #[cfg(feature = "toml")]
+        Self::Toml,
+        #[cfg(feature = "yaml")]
+        Self::Yaml,
+        #[cfg(feature = "ron")]
+        Self::Ron,
+        #[cfg(feature = "properties")]
+        Self::Properties,
+        #[cfg(feature = "hcl")]
+        Self::Hcl,
+        #[cfg(feature = "kdl")]
+        Self::Kdl,
+        #[cfg(feature = "xml")]
+        Self::Xml,
+        #[cfg(feature = "msgpack")]
+        Self::Msgpack,
+        #[cfg(feature = "cbor")]
+        Self::Cbor,
+    ];
 }
 
 /// An implementable trait for configuration storage.
@@ -45,16 +121,116 @@ pub enum ConfigurationVariant {
 pub trait Configuration: Serialize + for<'de> Deserialize<'de> {
     /// Creates a `ConfigurationBuilder` for this configuration.
     fn builder() -> ConfigurationBuilder<Self> {
-        ConfigurationBuilder(None)
+        ConfigurationBuilder::default()
+    }
+
+    /// The values used for fields that are missing from a file.
+    ///
+    /// This is generated by `#[derive(Configuration)]` from `#[cogwheel(default = "...")]`
+    /// and `#[cogwheel(default_fn = ...)]` on fields, and is empty otherwise:
+    /// ```
+    /// #[derive(Configuration, Serialize, Deserialize)]
+    /// struct ServerConfig {
+    ///     host: String,
+    ///     #[cogwheel(default = "8080")]
+    ///     port: u16,
+    ///     #[cogwheel(default_fn = num_cpus::get)]
+    ///     workers: usize,
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// This will fail if a default value can't be serialized.
+    fn defaults() -> Result<Document, Error> {
+        Ok(Document::Object(document::Map::new()))
+    }
+
+    /// The old names of fields, and fields that are deprecated.
+    /// Old names are moved to their field before the file becomes `Self`,
+    /// and using either sends a `Warning` to the builder's `on_warning` sink.
+    ///
+    /// This is generated by `#[derive(Configuration)]` from `#[cogwheel(alias = "...")]`
+    /// and `#[cogwheel(deprecated = "...")]` on fields, and is empty otherwise:
+    /// ```
+    /// #[derive(Configuration, Serialize, Deserialize)]
+    /// struct SomeBasicNestedConfig {
+    ///     #[cogwheel(alias = "some_number", deprecated = "renamed in 0.2")]
+    ///     some_int: i32,
+    ///     #[cogwheel(deprecated = "this does nothing anymore")]
+    ///     some_unsigned: Option<u32>,
+    /// }
+    /// ```
+    ///
+    /// Aliases are relative to the struct they're on,
+    /// so fields holding another `Configuration` need `#[cogwheel(nested)]`
+    /// for its aliases (and `defaults`) to be used.
+    #[must_use]
+    fn aliases() -> Vec<Alias> {
+        Vec::new()
     }
 }
 
 /// A builder for a `Configuration` struct.
 ///
 /// Either a `use_*` or `make_*` method must be called and succeed before any `with_*` method.
-/// If `self.0` is `None`, then `build` or any `with_*` will fail.
+/// If `self.data` is `None`, then `build` or any `with_*` will fail.
 /// Error handling is a must.
-pub struct ConfigurationBuilder<T: Serialize + for<'de> Deserialize<'de>>(Option<T>);
+///
+/// Options that change how files are read or written (like `expose_secrets`)
+/// must be set before the `use_*` or `make_*` method they should affect.
+pub struct ConfigurationBuilder<T: Serialize + for<'de> Deserialize<'de>> {
+    data: Option<T>,
+    file: Option<(PathBuf, Arc<dyn Format>)>,
+    formats: Vec<Arc<dyn Format>>,
+    expose_secrets: bool,
+    profile: Option<String>,
+    root: Option<String>,
+    lenient: Option<fn() -> T>,
+    migrations: Migrations,
+    write_migrations: bool,
+    migrated: Option<Document>,
+    layers: Option<Document>,
+    only_strings: bool,
+    provenance: HashMap<String, String>,
+    warning_sink: Option<WarningSink>,
+    #[cfg(feature = "encryption")]
+    decryption_key: Option<Key>,
+    #[cfg(feature = "encryption")]
+    decrypted: Vec<String>,
+    #[cfg(feature = "url")]
+    url: Option<UrlState>,
+    #[cfg(feature = "url")]
+    url_cache: Option<PathBuf>,
+}
+
+impl<T: Serialize + for<'de> Deserialize<'de>> Default for ConfigurationBuilder<T> {
+    fn default() -> Self {
+        Self {
+            data: None,
+            file: None,
+            formats: Vec::new(),
+            expose_secrets: false,
+            profile: None,
+            root: None,
+            lenient: None,
+            migrations: Migrations::default(),
+            write_migrations: false,
+            migrated: None,
+            layers: None,
+            only_strings: false,
+            provenance: HashMap::new(),
+            warning_sink: None,
+            #[cfg(feature = "encryption")]
+            decryption_key: None,
+            #[cfg(feature = "encryption")]
+            decrypted: Vec::new(),
+            #[cfg(feature = "url")]
+            url: None,
+            #[cfg(feature = "url")]
+            url_cache: None,
+        }
+    }
+}
 
 impl<T: Serialize + for<'de> Deserialize<'de> + Configuration> ConfigurationBuilder<T> {
     /// Attempts to build a `Configuration` from this builder.
@@ -79,10 +255,197 @@ impl<T: Serialize + for<'de> Deserialize<'de> + Configuration> ConfigurationBuil
     /// This will fail if:
     /// - A location (like `use_str`) hasn't been specified yet
     pub fn build(self) -> Result<T, Error> {
-        self.0
+        self.data
             .map_or_else(|| Err(Error::NoConfigurationSpecified), |data: T| Ok(data))
     }
 
+    /// Same as `build`, but borrows the `Configuration` so that this builder can be kept around,
+    /// e.g. to `reload` or `save` it later.
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - A location (like `use_str`) hasn't been specified yet
+    pub fn build_ref(&self) -> Result<&T, Error> {
+        self.data.as_ref().ok_or(Error::NoConfigurationSpecified)
+    }
+
+    /// Makes the `make_*` methods write the real values of any `Secret`s in `T`,
+    /// instead of the `[REDACTED]` placeholder.
+    /// Either way, the builder keeps the real values of the `data` it was given.
+    ///
+    /// ```
+    /// let config: DatabaseConfig = DatabaseConfig::builder()
+    ///     .expose_secrets()
+    ///     .make("./database.toml", &data, None)?
+    ///     .build()?;
+    /// ```
+    #[must_use]
+    pub const fn expose_secrets(mut self) -> Self {
+        self.expose_secrets = true;
+        self
+    }
+
+    /// Makes the `use_*` and `make_*` methods decrypt any `enc:v1:...` values with `key`.
+    /// Values can be encrypted with `Key::encrypt`,
+    /// and the ones that were decrypted are encrypted again when `save` or a `make_*` method writes them.
+    ///
+    /// ```
+    /// // password = "enc:v1:..."
+    /// let config: DatabaseConfig = DatabaseConfig::builder()
+    ///     .decrypt_with(Key::from_file("./cogwheel.key")?)
+    ///     .use_file("./database.toml", ConfigurationVariant::Toml)?
+    ///     .build()?;
+    /// ```
+    #[cfg(feature = "encryption")]
+    #[must_use]
+    pub fn decrypt_with(mut self, key: Key) -> Self {
+        self.decryption_key = Some(key);
+        self
+    }
+
+    /// Makes the `use_*` methods merge the profile called `name` over the rest of the file.
+    ///
+    /// Profiles live under the `profile` key, and only have to contain what's different from the base:
+    /// ```toml
+    /// some_string = "Hello, world!"
+    /// some_bool = true
+    ///
+    /// [profile.prod]
+    /// some_bool = false
+    /// ```
+    /// ```
+    /// let config: SomeBasicConfig = SomeBasicConfig::builder()
+    ///     .profile("prod")
+    ///     .use_file("./config.toml", ConfigurationVariant::Toml)?
+    ///     .build()?;
+    /// ```
+    #[must_use]
+    pub fn profile(mut self, name: &str) -> Self {
+        self.profile = Some(name.to_string());
+        self
+    }
+
+    /// Same as `profile`, but takes the profile name from the environment variable `name`.
+    /// If the variable isn't set, no profile is used.
+    ///
+    /// ```
+    /// // APP_PROFILE=prod
+    /// let config: SomeBasicConfig = SomeBasicConfig::builder()
+    ///     .profile_from_env("APP_PROFILE")
+    ///     .use_file("./config.toml", ConfigurationVariant::Toml)?
+    ///     .build()?;
+    /// ```
+    #[must_use]
+    pub fn profile_from_env(mut self, name: &str) -> Self {
+        if let Ok(profile) = env::var(name) {
+            self.profile = Some(profile);
+        }
+        self
+    }
+
+    /// Makes the `use_*` methods fill any fields missing from a file with their values from `T::default()`,
+    /// so files only need to contain what's different.
+    ///
+    /// ```
+    /// let config: SomeBasicConfig = SomeBasicConfig::builder()
+    ///     .lenient()
+    ///     .use_str("some_bool = true", ConfigurationVariant::Toml)?
+    ///     .build()?;
+    /// ```
+    #[must_use]
+    pub fn lenient(mut self) -> Self
+    where
+        T: Default,
+    {
+        self.lenient = Some(T::default);
+        self
+    }
+
+    /// Registers a migration that upgrades a file from version `from` to version `to`,
+    /// which runs on the parsed file before it becomes `T`.
+    ///
+    /// A file's version is its top level `version` key, and files without one are version 1.
+    /// Files are migrated one step at a time up to the newest `to` of every registered migration,
+    /// and the `make_*` methods write that version into new files.
+    ///
+    /// ```
+    /// // `some_int` used to be called `some_number`
+    /// let config: SomeBasicConfig = SomeBasicConfig::builder()
+    ///     .migration(1, 2, |document: &mut Document| {
+    ///         if let Some(nest) = document.get_mut("some_nest").and_then(Document::as_object_mut) {
+    ///             if let Some(number) = nest.remove("some_number") {
+    ///                 nest.insert("some_int".to_string(), number);
+    ///             }
+    ///         }
+    ///     })
+    ///     .use_file("./config.toml", ConfigurationVariant::Toml)?
+    ///     .build()?;
+    /// ```
+    #[must_use]
+    pub fn migration(
+        mut self,
+        from: u64,
+        to: u64,
+        migration: impl Fn(&mut Document) + Send + Sync + 'static,
+    ) -> Self {
+        self.migrations.push(from, to, Box::new(migration));
+        self
+    }
+
+    /// Makes `use_file` overwrite a file that had to be migrated with the migrated version.
+    /// The file is written as it was right after migrating, before profiles, decryption or defaults,
+    /// so those (and unknown keys) are kept, but comments are lost.
+    ///
+    /// ```
+    /// let config: SomeBasicConfig = SomeBasicConfig::builder()
+    ///     .migration(1, 2, migrate_to_v2)
+    ///     .write_migrations()
+    ///     .use_file("./config.toml", ConfigurationVariant::Toml)?
+    ///     .build()?;
+    /// ```
+    #[must_use]
+    pub const fn write_migrations(mut self) -> Self {
+        self.write_migrations = true;
+        self
+    }
+
+    /// Sends any `Warning`s from the `use_*` methods (like deprecated keys) to `sink`.
+    /// Without a sink, warnings are dropped.
+    ///
+    /// ```
+    /// let config: SomeBasicConfig = SomeBasicConfig::builder()
+    ///     .on_warning(|warning: &Warning| log::warn!("{warning}"))
+    ///     .use_file("./config.toml", ConfigurationVariant::Toml)?
+    ///     .build()?;
+    /// ```
+    #[must_use]
+    pub fn on_warning(mut self, sink: impl Fn(&Warning) + Send + Sync + 'static) -> Self {
+        self.warning_sink = Some(Box::new(sink));
+        self
+    }
+
+    /// Registers a `Format`, so files with one of its extensions can be guessed as it.
+    /// Registered formats are guessed before the built-in ones, and later ones before earlier ones.
+    ///
+    /// ```
+    /// let config: SomeFlatConfig = SomeFlatConfig::builder()
+    ///     .register_format(KeyValue)
+    ///     .make_default("./config.kv", None)?
+    ///     .build()?;
+    /// ```
+    #[must_use]
+    pub fn register_format(mut self, format: impl Format + 'static) -> Self {
+        self.formats.push(Arc::new(format));
+        self
+    }
+
+    /// Sends `warning` to the sink from `on_warning`, if there is one.
+    fn warn(&self, warning: &Warning) {
+        if let Some(sink) = &self.warning_sink {
+            sink(warning);
+        }
+    }
+
     /// Attempts to parse an `&str` into a configuration struct, `T`.
     ///
     /// ```
@@ -103,219 +466,911 @@ impl<T: Serialize + for<'de> Deserialize<'de> + Configuration> ConfigurationBuil
     ///
     /// # Errors
     /// This will fail if the string is:
-    /// - Missing an entry that isn't marked with an `Option<_>`
+    /// - Missing an entry that isn't marked with an `Option<_>` (and has no default, see `lenient`)
     /// - Malformed (either of wrong variant or otherwise malformed/corrupt)
-    pub fn use_str(mut self, data: &str, variant: ConfigurationVariant) -> Result<Self, Error> {
-        self.0 = Some(Self::gen_from_str(data, variant)?);
+    pub fn use_str(self, data: &str, variant: impl Format) -> Result<Self, Error> {
+        self.use_bytes(data.as_bytes(), variant)
+    }
 
-        Ok(self)
+    /// Parses bytes into `T`, going through a `Document` if anything has to change it first.
+    /// This replaces anything added with `add_source`.
+    fn load(&mut self, data: &[u8], format: &dyn Format) -> Result<T, Error> {
+        self.clear_sources();
+        self.only_strings = format.only_strings();
+        if let Some(variant) = format.variant() {
+            if T::aliases().is_empty()
+                && !self.transforms_document()
+                && self.gen_defaults()?.is_none()
+            {
+                return gen_from_slice(data, variant);
+            }
+        }
+
+        self.load_root(format.parse_bytes(data)?)
     }
 
-    /// Parses `&str` into `T`.
-    fn gen_from_str(data: &str, variant: ConfigurationVariant) -> Result<T, Error> {
-        match variant {
-            #[cfg(feature = "json")]
-            ConfigurationVariant::Json => Ok(serde_json::from_str::<T>(data)?),
-            #[cfg(feature = "toml")]
-            ConfigurationVariant::Toml => Ok(toml::from_str::<T>(data)?),
-            #[cfg(feature = "yaml")]
-            ConfigurationVariant::Yaml => Ok(serde_yml::from_str::<T>(data)?),
+    /// Forgets anything added with `add_source` or `use_url`, before something replaces them.
+    fn clear_sources(&mut self) {
+        self.layers = None;
+        self.only_strings = false;
+        self.provenance.clear();
+        #[cfg(feature = "url")]
+        {
+            self.url = None;
         }
     }
 
-    /// Converts `T` into `String`.
-    fn gen_to_string(data: &T, variant: ConfigurationVariant) -> Result<String, Error> {
-        match variant {
-            #[cfg(feature = "json")]
-            ConfigurationVariant::Json => Ok(serde_json::to_string_pretty(&data)?),
-            #[cfg(feature = "toml")]
-            ConfigurationVariant::Toml => Ok(toml::to_string_pretty(&data)?),
-            #[cfg(feature = "yaml")]
-            ConfigurationVariant::Yaml => Ok(serde_yml::to_string(&data)?),
+    /// Takes the table at the key from `use_file_at` out of a whole file, then converts it like `load_document`.
+    fn load_root(&mut self, mut document: Document) -> Result<T, Error> {
+        if let Some(root) = &self.root {
+            document = document::remove_path(&mut document, root)
+                .ok_or_else(|| Error::MissingPath(root.clone()))?;
+        }
+        self.load_document(document)
+    }
+
+    /// Converts a `Document` into `T`, applying everything set on this builder first.
+    fn load_document(&mut self, mut document: Document) -> Result<T, Error> {
+        let defaults: Option<Document> = self.gen_defaults()?;
+        let aliases: Vec<Alias> = T::aliases();
+        self.transform_document(&mut document)?;
+        for alias in &aliases {
+            alias.apply(&mut document, &|warning: &Warning| self.warn(warning));
+        }
+        if let Some(mut defaults) = defaults {
+            document::merge(&mut defaults, document);
+            document = defaults;
+        }
+        if self.only_strings {
+            document::from_strings(document)
+        } else {
+            document::from_document(document)
+        }
+    }
+
+    /// Gets what a file should be merged over, from `lenient` and `Configuration::defaults`.
+    fn gen_defaults(&self) -> Result<Option<Document>, Error> {
+        let fields: Document = T::defaults()?;
+        let Some(default) = self.lenient else {
+            return Ok(fields
+                .as_object()
+                .map_or(true, |fields: &document::Map<String, Document>| {
+                    !fields.is_empty()
+                })
+                .then_some(fields));
+        };
+
+        // `Secret`s have to be exposed, or they'd default to their placeholder.
+        let mut defaults: Document = crate::secret::exposed(|| document::to_document(&default()))?;
+        document::merge(&mut defaults, fields);
+        Ok(Some(defaults))
+    }
+
+    /// Whether `transform_document` would do anything.
+    fn transforms_document(&self) -> bool {
+        #[cfg(feature = "encryption")]
+        if self.decryption_key.is_some() {
+            return true;
+        }
+
+        self.root.is_some() || self.profile.is_some() || !self.migrations.is_empty()
+    }
+
+    /// Applies everything set on this builder to a `Document` before it becomes `T`.
+    fn transform_document(&mut self, document: &mut Document) -> Result<(), Error> {
+        let migrated: bool = self.migrations.apply(document)?;
+        // Kept before anything else changes it, so `write_migrations` only writes what the migrations did.
+        self.migrated = (migrated && self.write_migrations).then(|| document.clone());
+
+        if let Some(profile) = &self.profile {
+            document::apply_profile(document, profile)?;
+        }
+
+        #[cfg(feature = "encryption")]
+        {
+            self.decrypted = match &self.decryption_key {
+                Some(key) => key.decrypt_document(document)?,
+                None => Vec::new(),
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Whether `gen_to_vec` has to change `T` before it's written.
+    fn transforms_output(&self) -> bool {
+        #[cfg(feature = "encryption")]
+        if !self.decrypted.is_empty() {
+            return true;
+        }
+
+        self.root.is_some() || !self.migrations.is_empty()
+    }
+
+    /// Encrypts the values in `document` that were decrypted when loading again,
+    /// using their real values from `data` even if they're `Secret`s.
+    #[cfg(feature = "encryption")]
+    fn encrypt_document(&self, data: &T, document: &mut Document) -> Result<(), Error> {
+        let Some(key) = &self.decryption_key else {
+            return Ok(());
+        };
+        if self.decrypted.is_empty() {
+            return Ok(());
+        }
+
+        let exposed: Document = crate::secret::exposed(|| document::to_document(data))?;
+        for pointer in &self.decrypted {
+            if let (Some(target), Some(Document::String(value))) =
+                (document.pointer_mut(pointer), exposed.pointer(pointer))
+            {
+                *target = key.encrypt(value)?.into();
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts `T` into bytes, exposing any `Secret`s if `expose_secrets` was called,
+    /// encrypting anything that `decrypt_with` decrypted again,
+    /// and adding the current version if there are any migrations.
+    /// After `use_file_at`, `T` is put at its key in `existing` (the file's current contents) instead.
+    fn gen_to_vec(
+        &self,
+        data: &T,
+        format: &dyn Format,
+        existing: Option<&[u8]>,
+    ) -> Result<Vec<u8>, Error> {
+        let serialize = || -> Result<Vec<u8>, Error> {
+            if let Some(variant) = format.variant() {
+                if !self.transforms_output() {
+                    return gen_to_vec(data, variant);
+                }
+            }
+
+            let mut document: Document = document::to_document(data)?;
+            #[cfg(feature = "encryption")]
+            self.encrypt_document(data, &mut document)?;
+            if !self.migrations.is_empty() {
+                self.migrations.stamp(&mut document);
+            }
+            if let Some(root) = &self.root {
+                let mut file: Document = match existing {
+                    Some(existing) => format.parse_bytes(existing)?,
+                    None => Document::Object(document::Map::new()),
+                };
+                document::insert_path(&mut file, root, document);
+                document = file;
+            }
+            format.serialize_bytes(&document)
+        };
+
+        if self.expose_secrets {
+            crate::secret::exposed(serialize)
+        } else {
+            serialize()
         }
     }
 
-    /// Attempts to read a file at `path` to type `T`.
-    /// This method can guess the variant based off the path if you specify `variant` as `None`.
+    /// Converts a file that was migrated for `write_migrations` back into bytes,
+    /// putting it back at its key in `existing` (the file before migrating) after `use_file_at`.
+    fn gen_migrated(
+        &self,
+        document: Document,
+        format: &dyn Format,
+        existing: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let Some(root) = &self.root else {
+            return format.serialize_bytes(&document);
+        };
+
+        let mut file: Document = format.parse_bytes(existing)?;
+        document::insert_path(&mut file, root, document);
+        format.serialize_bytes(&file)
+    }
+
+    /// Attempts to parse bytes into a configuration struct, `T`.
+    /// Byte order marks are skipped, and UTF-16 is accepted if it starts with one.
+    /// Binary formats, like CBOR, are read as they are.
     ///
     /// ```
     /// let config: SomeBasicConfig = SomeBasicConfig::builder()
-    ///     .use_file("./config.toml", None)?
+    ///     .use_bytes(include_bytes!("./config.toml"), ConfigurationVariant::Toml)?
     ///     .build()?;
     /// ```
     ///
     /// # Errors
-    /// This will fail if:
-    /// - The file does not exist/is a directory
-    /// - The file can't be read
-    pub fn use_file<S: AsRef<Path> + ?Sized>(
-        mut self,
-        path: &S,
-        variant: ConfigurationVariant,
-    ) -> Result<Self, Error> {
-        let mut output: String = String::new();
-        File::open(path)?.read_to_string(&mut output)?;
+    /// This will fail if the bytes are:
+    /// - Not UTF-8 (or UTF-16 with a byte order mark), for text formats
+    /// - Missing an entry that isn't marked with an `Option<_>`
+    /// - Malformed (either of wrong variant or otherwise malformed/corrupt)
+    pub fn use_bytes(mut self, data: &[u8], variant: impl Format) -> Result<Self, Error> {
+        self.root = None;
+        self.data = Some(self.load(data, &variant)?);
+        self.file = None;
 
-        self.0 = Some(Self::gen_from_str(&output, variant)?);
         Ok(self)
     }
 
-    fn guess_file_variant(path: &Path) -> Result<ConfigurationVariant, Error> {
-        match path
-            .extension()
-            .map(|x: &OsStr| x.to_string_lossy().to_lowercase())
-            .as_deref()
-        {
-            #[cfg(feature = "json")]
-            Some("json") => Ok(ConfigurationVariant::Json),
-            #[cfg(feature = "toml")]
-            Some("toml") => Ok(ConfigurationVariant::Toml),
-            #[cfg(feature = "yaml")]
-            Some("yaml" | "yml") => Ok(ConfigurationVariant::Yaml),
-            None | Some(_) => Err(Error::FileIsDirectory),
-        }
-    }
-
-    /// Attempts to make a configuration file of type `variant` at `path` with the specified data from `data`.
-    /// If you want to overwrite an already existing file, you should use `make_override` instead.
-    /// This method can guess the variant based off the path if you specify `variant` as `None`.
+    /// Attempts to read everything from `reader` into a configuration struct, `T`.
     ///
     /// ```
-    /// let data: SomeBasicConfig = SomeBasicConfig {
-    ///     some_string: "Hello, world!".to_string(),
-    ///     some_bool: true,
-    ///     some_nest: SomeBasicNestedConfig {
-    ///         some_int: -4,
-    ///         some_float: 3.14_159_26,
-    ///         some_unsigned: 2_147_483_648,
-    ///     },
-    /// }
-    ///
     /// let config: SomeBasicConfig = SomeBasicConfig::builder()
-    ///     .make("./config.toml", data, ConfigurationVariant::Toml)?
+    ///     .use_reader(archive.by_name("config.toml")?, ConfigurationVariant::Toml)?
     ///     .build()?;
     /// ```
     ///
     /// # Errors
     /// This will fail if:
-    /// - The file already exists
-    /// - `path` is a directory
-    /// - The data was corrupt/malformed after the write
-    pub fn make<P: AsRef<Path> + ?Sized>(
-        mut self,
-        path: &P,
-        data: &T,
-        variant: Option<ConfigurationVariant>,
-    ) -> Result<Self, Error> {
-        let variant: ConfigurationVariant = variant
-            .or_else(|| Self::guess_file_variant(path.as_ref()).ok())
-            .ok_or(Error::CouldNotGuess)?;
-        let mut file: File = File::create_new(path)?;
-        file.write_all(Self::gen_to_string(data, variant)?.as_bytes())?;
-        self = self.use_file(path, variant)?;
-        Ok(self)
+    /// - `reader` fails
+    /// - The data isn't valid, see `use_bytes`
+    pub fn use_reader<R: Read>(self, mut reader: R, variant: impl Format) -> Result<Self, Error> {
+        let mut output: Vec<u8> = Vec::new();
+        reader.read_to_end(&mut output)?;
+
+        self.use_bytes(&output, variant)
     }
 
-    /// Attempts to make a configuration file of type `variant` at `path` with the default data for `T`.
-    /// If you want to overwrite an already existing file, you should use `make_default_override` instead.
-    /// This method can guess the variant based off the path if you specify `variant` as `None`.
+    /// Attempts to add a `Source` over any sources added before it, then parse them into `T`.
+    /// Tables are merged key by key, so a source only has to have the values it changes.
+    ///
+    /// If a configuration was already loaded by a `use_*` or `make_*` method, sources are added over it,
+    /// but using one of those methods afterwards replaces every source.
     ///
     /// ```
     /// let config: SomeBasicConfig = SomeBasicConfig::builder()
-    ///     .make_default("./config.toml", None)?
+    ///     .add_source(FileSource::new("./config.toml", ConfigurationVariant::Toml))?
+    ///     .add_source(EnvSource::new("APP"))?
     ///     .build()?;
     /// ```
     ///
     /// # Errors
     /// This will fail if:
-    /// - The file already exists
-    /// - `path` is a directory
-    /// - The data was corrupt/malformed after the write
-    pub fn make_default<P: AsRef<Path> + ?Sized>(
-        mut self,
-        path: &P,
-        variant: Option<ConfigurationVariant>,
-    ) -> Result<Self, Error>
-    where
-        T: Default,
-    {
-        let variant: ConfigurationVariant = variant
-            .or_else(|| Self::guess_file_variant(path.as_ref()).ok())
-            .ok_or(Error::CouldNotGuess)?;
-        let mut file: File = File::create_new(path)?;
-        file.write_all(Self::gen_to_string(&T::default(), variant)?.as_bytes())?;
-        self = self.use_file(path, variant)?;
+    /// - `source` fails, with an error naming it
+    /// - The sources together aren't a valid `T`
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn add_source(mut self, source: impl Source) -> Result<Self, Error> {
+        let label: String = source.label();
+        let document: Document = source
+            .load()
+            .map_err(|error: Error| Error::SourceError(label.clone(), Box::new(error)))?;
+
+        let mut layers: Document = match (self.layers.take(), &self.data) {
+            (Some(layers), _) => layers,
+            // `Secret`s have to be exposed, or they'd come back as their placeholder.
+            (None, Some(data)) => crate::secret::exposed(|| document::to_document(data))?,
+            (None, None) => Document::Object(document::Map::new()),
+        };
+        let mut paths: Vec<String> = Vec::new();
+        source::leaf_paths("", &document, &mut paths);
+        document::merge(&mut layers, document);
+
+        self.only_strings |= source.only_strings();
+        self.data = Some(self.load_document(layers.clone())?);
+        self.layers = Some(layers);
+        self.file = None;
+        for path in paths {
+            self.provenance.insert(path, label.clone());
+        }
         Ok(self)
     }
 
-    /// Attempts to make a configuration file of type `variant` at `path` with the specified data from `data`.
-    /// If you want to overwrite an already existing file, you should use `make_override` instead.
-    /// This method can guess the variant based off the path if you specify `variant` as `None`.
+    /// Gets the label of the `Source` that a dotted path like `some_nest.some_int` came from,
+    /// or of the source that set the closest table around it.
+    /// This is `None` for anything that didn't come from `add_source`.
     ///
     /// ```
-    /// let data: SomeBasicConfig = SomeBasicConfig {
-    ///     some_string: "Hello, world!".to_string(),
-    ///     some_bool: true,
-    ///     some_nest: SomeBasicNestedConfig {
-    ///         some_int: -4,
-    ///         some_float: 3.14_159_26,
-    ///         some_unsigned: 2_147_483_648,
-    ///     },
-    /// }
+    /// let builder: ConfigurationBuilder<SomeBasicConfig> = SomeBasicConfig::builder()
+    ///     .add_source(FileSource::new("./config.toml", ConfigurationVariant::Toml))?
+    ///     .add_source(EnvSource::new("APP"))?;
+    ///
+    /// println!("some_int is from {:?}", builder.source_of("some_nest.some_int"));
+    /// ```
+    #[must_use]
+    pub fn source_of(&self, key: &str) -> Option<&str> {
+        let mut key: &str = key;
+        loop {
+            if let Some(label) = self.provenance.get(key) {
+                return Some(label);
+            }
+            key = key.rsplit_once('.')?.0;
+        }
+    }
+
+    /// Attempts to read a file at `path` to type `T`, see `use_file_guessed` to guess its format instead.
+    /// If `path` is `-`, stdin is read instead.
     ///
+    /// With the `compression` feature, files ending in `.gz` or `.zst` are decompressed first,
+    /// and the `make_*` methods compress them. Their format is guessed from the extension before that,
+    /// like YAML for `config.yaml.gz`.
+    ///
+    /// ```
     /// let config: SomeBasicConfig = SomeBasicConfig::builder()
-    ///     .make_override("./config.toml", data, ConfigurationVariant::Toml)?
+    ///     .use_file("./config.toml", ConfigurationVariant::Toml)?
     ///     .build()?;
     /// ```
     ///
     /// # Errors
     /// This will fail if:
-    /// - `path` is a directory
-    /// - The data was corrupt/malformed after the write
-    pub fn make_override<P: AsRef<Path> + ?Sized>(
+    /// - The file does not exist/is a directory
+    /// - The file can't be read
+    pub fn use_file<S: AsRef<Path> + ?Sized>(
         mut self,
-        path: &P,
-        data: &T,
-        variant: Option<ConfigurationVariant>,
+        path: &S,
+        variant: impl Format + 'static,
     ) -> Result<Self, Error> {
-        let variant: ConfigurationVariant = variant
-            .or_else(|| Self::guess_file_variant(path.as_ref()).ok())
-            .ok_or(Error::CouldNotGuess)?;
-        let mut file: File = File::create(path)?;
-        file.write_all(Self::gen_to_string(data, variant)?.as_bytes())?;
-        self = self.use_file(path, variant)?;
-        Ok(self)
+        self.root = None;
+        self.use_file_as(path.as_ref(), Arc::new(variant))
     }
 
-    /// Attempts to make a configuration file of type `variant` at `path` with the default data for `T`.
-    /// If you want to overwrite an already existing file, you should use `make_default_override` instead.
-    /// This method can guess the variant based off the path if you specify `variant` as `None`.
+    /// Same as `use_file`, but only reads the table at a dotted `key` of the file into `T`,
+    /// like a tool's section of a shared file.
+    /// This lasts until the next `use_*` method, so `reload` reads the same table,
+    /// and `save` and the `make_*` methods write `T` back into it, keeping the rest of the file.
     ///
     /// ```
     /// let config: SomeBasicConfig = SomeBasicConfig::builder()
-    ///     .make_default_override("./config.toml", None)?
+    ///     .use_file_at("./Cargo.toml", "package.metadata.some_tool", ConfigurationVariant::Toml)?
     ///     .build()?;
     /// ```
     ///
     /// # Errors
     /// This will fail if:
-    /// - `path` is a directory
-    /// - The data was corrupt/malformed after the write
-    pub fn make_default_override<P: AsRef<Path> + ?Sized>(
+    /// - The file can't be read, see `use_file`
+    /// - There's nothing at `key`
+    pub fn use_file_at<S: AsRef<Path> + ?Sized>(
         mut self,
-        path: &P,
-        variant: Option<ConfigurationVariant>,
-    ) -> Result<Self, Error>
+        path: &S,
+        key: &str,
+        variant: impl Format + 'static,
+    ) -> Result<Self, Error> {
+        self.root = Some(key.to_string());
+        self.use_file_as(path.as_ref(), Arc::new(variant))
+    }
+
+    /// Same as `use_str`, but only reads the table at a dotted `key` of `data` into `T`.
+    ///
+    /// ```
+    /// let file: &str = r#"
+    /// [tool.some_tool]
+    /// some_string = "Hello, world!"
+    /// some_bool = true
+    /// "#;
+    ///
+    /// let config: SomeBasicConfig = SomeBasicConfig::builder()
+    ///     .use_str_at(file, "tool.some_tool", ConfigurationVariant::Toml)?
+    ///     .build()?;
+    /// ```
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - The string isn't valid, see `use_str`
+    /// - There's nothing at `key`
+    pub fn use_str_at(
+        mut self,
+        data: &str,
+        key: &str,
+        variant: impl Format,
+    ) -> Result<Self, Error> {
+        self.root = Some(key.to_string());
+        self.data = Some(self.load(data.as_bytes(), &variant)?);
+        self.file = None;
+
+        Ok(self)
+    }
+
+    /// Attempts to read the table at a dotted `key` of an already parsed `SharedFile` into `T`,
+    /// so that several `Configuration`s can come from one file without parsing it again.
+    /// Like `use_file_at`, `save` and `reload` work on just that table if it was opened from a file.
+    ///
+    /// ```
+    /// let file: SharedFile = SharedFile::open("./config.toml", ConfigurationVariant::Toml)?;
+    ///
+    /// let server: ServerConfig = ServerConfig::builder().use_section(&file, "server")?.build()?;
+    /// let database: DatabaseConfig = DatabaseConfig::builder()
+    ///     .lenient()
+    ///     .use_section(&file, "database")?
+    ///     .build()?;
+    /// ```
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - There's nothing at `key`
+    /// - The table isn't valid for `T`
+    pub fn use_section(mut self, file: &SharedFile, key: &str) -> Result<Self, Error> {
+        self.clear_sources();
+        self.root = Some(key.to_string());
+        let document: Document = document::get_path(&file.document, key)
+            .cloned()
+            .ok_or_else(|| Error::MissingPath(key.to_string()))?;
+        self.only_strings = file.only_strings;
+        self.data = Some(self.load_document(document)?);
+        self.file.clone_from(&file.file);
+
+        Ok(self)
+    }
+
+    /// Attempts to read every file in the directory at `path` into its own `T`,
+    /// keyed by its name without the format's extension (or a compressed file's two),
+    /// like `example.com` for `sites.d/example.com.toml`.
+    /// Formats are guessed from extensions like `use_file_guessed` does,
+    /// and files it can't guess (like a `README`), hidden files and directories are skipped.
+    ///
+    /// A file named `_defaults` (with any extension) isn't an instance, every other file is merged over it,
+    /// so it only needs the values they share. Options like `lenient` and `profile` apply to every file.
+    ///
+    /// ```
+    /// let sites: BTreeMap<String, SiteConfig> = SiteConfig::builder().use_instances("./sites.d/")?;
+    /// ```
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - The directory can't be read
+    /// - A file can't be read, or isn't a valid `T` over the defaults, with an error naming it
+    /// - Two files have the same name, like `example.toml` and `example.yaml`
+    pub fn use_instances<P: AsRef<Path> + ?Sized>(
+        mut self,
+        path: &P,
+    ) -> Result<BTreeMap<String, T>, Error> {
+        let mut files: BTreeMap<String, (PathBuf, Arc<dyn Format>)> = BTreeMap::new();
+        for entry in fs::read_dir(path)? {
+            let path: PathBuf = entry?.path();
+            let Some(name) = path
+                .file_name()
+                .map(|x: &OsStr| x.to_string_lossy().into_owned())
+            else {
+                continue;
+            };
+            if name.starts_with('.') || !path.is_file() {
+                continue;
+            }
+            let Ok(format) = self.guess_file_variant(&path) else {
+                continue;
+            };
+
+            // `example.com.toml.gz` is `example.com`.
+            #[cfg(feature = "compression")]
+            let name: String = match Compression::of(&path) {
+                Some(_) => name
+                    .rsplit_once('.')
+                    .map_or_else(|| name.clone(), |(stem, _): (&str, &str)| stem.to_string()),
+                None => name,
+            };
+            let stem: String = name
+                .rsplit_once('.')
+                .map_or(name.as_str(), |(stem, _): (&str, &str)| stem)
+                .to_string();
+            if files.insert(stem.clone(), (path, format)).is_some() {
+                return Err(Error::DuplicateInstance(stem));
+            }
+        }
+
+        let source_error = |path: &Path, error: Error| {
+            Error::SourceError(path.display().to_string(), Box::new(error))
+        };
+        let (defaults, defaults_only_strings): (Option<Document>, bool) =
+            match files.remove(INSTANCE_DEFAULTS) {
+                Some((path, format)) => (
+                    Some(
+                        read_file(&path, format.as_ref())
+                            .map_err(|error: Error| source_error(&path, error))?,
+                    ),
+                    format.only_strings(),
+                ),
+                None => (None, false),
+            };
+
+        self.root = None;
+        self.clear_sources();
+        let mut instances: BTreeMap<String, T> = BTreeMap::new();
+        for (name, (path, format)) in files {
+            self.only_strings = defaults_only_strings || format.only_strings();
+            let instance: T = read_file(&path, format.as_ref())
+                .and_then(|document: Document| {
+                    let document: Document = match &defaults {
+                        Some(defaults) => {
+                            let mut merged: Document = defaults.clone();
+                            document::merge(&mut merged, document);
+                            merged
+                        }
+                        None => document,
+                    };
+                    self.load_document(document)
+                })
+                .map_err(|error: Error| source_error(&path, error))?;
+            instances.insert(name, instance);
+        }
+        Ok(instances)
+    }
+
+    /// Attempts to read a file at `path` to type `T`, guessing its format.
+    /// The format is guessed from the extension (see `register_format`),
+    /// or from the contents if that doesn't work, like for stdin (`-`).
+    ///
+    /// ```
+    /// let config: SomeBasicConfig = SomeBasicConfig::builder()
+    ///     .use_file_guessed(&args.config)?
+    ///     .build()?;
+    /// ```
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - The file does not exist/is a directory
+    /// - The file can't be read
+    /// - The format can't be guessed
+    pub fn use_file_guessed<S: AsRef<Path> + ?Sized>(mut self, path: &S) -> Result<Self, Error> {
+        self.root = None;
+        let path: &Path = path.as_ref();
+        if let Ok(format) = self.guess_file_variant(path) {
+            return self.use_file_as(path, format);
+        }
+
+        let mut output: Vec<u8> = Vec::new();
+        if path == Path::new(STDIN_PATH) {
+            io::stdin().lock().read_to_end(&mut output)?;
+        } else {
+            File::open(path)?.read_to_end(&mut output)?;
+        }
+        #[cfg(feature = "compression")]
+        let output: Vec<u8> = decompress(path, output)?;
+        let data: Cow<'_, str> = decode(&output)?;
+        let format: Arc<dyn Format> = self.sniff_variant(&data).ok_or(Error::CouldNotGuess)?;
+        self.use_str(&data, format.as_ref())
+    }
+
+    fn use_file_as(mut self, path: &Path, format: Arc<dyn Format>) -> Result<Self, Error> {
+        let mut output: Vec<u8> = Vec::new();
+        if path == Path::new(STDIN_PATH) {
+            io::stdin().lock().read_to_end(&mut output)?;
+            self.data = Some(self.load(&output, format.as_ref())?);
+            self.file = None;
+            return Ok(self);
+        }
+
+        File::open(path)?.read_to_end(&mut output)?;
+        #[cfg(feature = "compression")]
+        let output: Vec<u8> = decompress(path, output)?;
+
+        self.data = Some(self.load(&output, format.as_ref())?);
+        if let Some(document) = self.migrated.take() {
+            let output: Vec<u8> = self.gen_migrated(document, format.as_ref(), &output)?;
+            #[cfg(feature = "compression")]
+            let output: Vec<u8> = compress(path, output)?;
+            fs::write(path, output)?;
+        }
+        self.file = Some((path.to_path_buf(), format));
+        Ok(self)
+    }
+
+    /// Finds the format for `path` from its extension, trying registered formats first.
+    pub(crate) fn guess_file_variant(&self, path: &Path) -> Result<Arc<dyn Format>, Error> {
+        let name: String = path
+            .file_name()
+            .map(|x: &OsStr| x.to_string_lossy().to_lowercase())
+            .ok_or(Error::CouldNotGuess)?;
+        // Compressed files are guessed from the extension inside, like `yaml` for `config.yaml.gz`.
+        #[cfg(feature = "compression")]
+        let name: String = match Compression::of(path) {
+            Some(_) => Path::new(&name)
+                .file_stem()
+                .map(|x: &OsStr| x.to_string_lossy().into_owned())
+                .ok_or(Error::CouldNotGuess)?,
+            None => name,
+        };
+        // `.env` and `.env.local` are named like an extension, but are `env` files.
+        let extension: String = if name == ".env" || name.starts_with(".env.") {
+            "env".to_string()
+        } else {
+            Path::new(&name)
+                .extension()
+                .map(|x: &OsStr| x.to_string_lossy().into_owned())
+                .ok_or(Error::CouldNotGuess)?
+        };
+
+        self.formats()
+            .find(|format: &Arc<dyn Format>| format.extensions().contains(&extension.as_str()))
+            .ok_or(Error::CouldNotGuess)
+    }
+
+    /// Finds the first format that `data` looks like, trying registered formats first.
+    pub(crate) fn sniff_variant(&self, data: &str) -> Option<Arc<dyn Format>> {
+        self.formats()
+            .find(|format: &Arc<dyn Format>| format.sniff(data))
+    }
+
+    /// Every format that can be guessed, in the order they're tried in.
+    pub(crate) fn formats(&self) -> impl Iterator<Item = Arc<dyn Format>> + '_ {
+        self.formats.iter().rev().cloned().chain(
+            ConfigurationVariant::ALL
+                .iter()
+                .map(|variant: &ConfigurationVariant| Arc::new(*variant) as Arc<dyn Format>),
+        )
+    }
+
+    /// Uses `variant`, or guesses the format from `path` if it's `None`.
+    fn resolve_format(
+        &self,
+        path: &Path,
+        variant: impl Into<FormatChoice>,
+    ) -> Result<Arc<dyn Format>, Error> {
+        match variant.into().0 {
+            Some(format) => Ok(format),
+            None => self.guess_file_variant(path),
+        }
+    }
+
+    /// Writes `data` to `path`, failing if it already exists unless `overwrite` is set,
+    /// then reads it back like the `make_*` methods, unless that would lose any `Secret`s.
+    fn make_internal(
+        self,
+        path: &Path,
+        data: &T,
+        format: Arc<dyn Format>,
+        overwrite: bool,
+    ) -> Result<Self, Error> {
+        let existing: Option<Vec<u8>> = match self.root {
+            Some(_) => match fs::read(path) {
+                Ok(existing) => Some(existing),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+                Err(error) => return Err(error.into()),
+            },
+            None => None,
+        };
+        #[cfg(feature = "compression")]
+        let existing: Option<Vec<u8>> = existing
+            .map(|existing: Vec<u8>| decompress(path, existing))
+            .transpose()?;
+
+        let (output, redacted): (Result<Vec<u8>, Error>, bool) =
+            crate::secret::redacted(|| self.gen_to_vec(data, format.as_ref(), existing.as_deref()));
+        #[cfg(feature = "compression")]
+        let output: Result<Vec<u8>, Error> =
+            output.and_then(|output: Vec<u8>| compress(path, output));
+        let output: Vec<u8> = output?;
+        let mut file: File = if overwrite {
+            File::create(path)?
+        } else {
+            File::create_new(path)?
+        };
+        file.write_all(&output)?;
+        if redacted {
+            return self.keep_made(path, data, format);
+        }
+        self.use_file_as(path, format)
+    }
+
+    /// Keeps `data` as `T` after a `make_*` method wrote it to `path` with placeholders for its `Secret`s,
+    /// since reading the file back would give `T` the placeholders instead of the real values.
+    fn keep_made(mut self, path: &Path, data: &T, format: Arc<dyn Format>) -> Result<Self, Error> {
+        let document: Document = crate::secret::exposed(|| document::to_document(data))?;
+        self.clear_sources();
+        self.data = Some(serde_json::from_value(document)?);
+        self.file = Some((path.to_path_buf(), format));
+        Ok(self)
+    }
+
+    /// Attempts to make a configuration file of type `variant` at `path` with the specified data from `data`.
+    /// If you want to overwrite an already existing file, you should use `make_override` instead.
+    /// This method can guess the variant based off the path if you specify `variant` as `None`,
+    /// and `variant` can also be any other `Format`, see `FormatChoice`.
+    ///
+    /// ```
+    /// let data: SomeBasicConfig = SomeBasicConfig {
+    ///     some_string: "Hello, world!".to_string(),
+    ///     some_bool: true,
+    ///     some_nest: SomeBasicNestedConfig {
+    ///         some_int: -4,
+    ///         some_float: 3.14_159_26,
+    ///         some_unsigned: 2_147_483_648,
+    ///     },
+    /// }
+    ///
+    /// let config: SomeBasicConfig = SomeBasicConfig::builder()
+    ///     .make("./config.toml", data, ConfigurationVariant::Toml)?
+    ///     .build()?;
+    /// ```
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - The file already exists
+    /// - `path` is a directory
+    /// - The data was corrupt/malformed after the write
+    pub fn make<P: AsRef<Path> + ?Sized>(
+        self,
+        path: &P,
+        data: &T,
+        variant: impl Into<FormatChoice>,
+    ) -> Result<Self, Error> {
+        let format: Arc<dyn Format> = self.resolve_format(path.as_ref(), variant)?;
+        self.make_internal(path.as_ref(), data, format, false)
+    }
+
+    /// Attempts to make a configuration file of type `variant` at `path` with the default data for `T`.
+    /// If you want to overwrite an already existing file, you should use `make_default_override` instead.
+    /// This method can guess the variant based off the path if you specify `variant` as `None`,
+    /// and `variant` can also be any other `Format`, see `FormatChoice`.
+    ///
+    /// ```
+    /// let config: SomeBasicConfig = SomeBasicConfig::builder()
+    ///     .make_default("./config.toml", None)?
+    ///     .build()?;
+    /// ```
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - The file already exists
+    /// - `path` is a directory
+    /// - The data was corrupt/malformed after the write
+    pub fn make_default<P: AsRef<Path> + ?Sized>(
+        self,
+        path: &P,
+        variant: impl Into<FormatChoice>,
+    ) -> Result<Self, Error>
     where
         T: Default,
     {
-        let variant: ConfigurationVariant = variant
-            .or_else(|| Self::guess_file_variant(path.as_ref()).ok())
-            .ok_or(Error::CouldNotGuess)?;
-        let mut file: File = File::create(path)?;
-        file.write_all(Self::gen_to_string(&T::default(), variant)?.as_bytes())?;
-        self = self.use_file(path, variant)?;
-        Ok(self)
+        let format: Arc<dyn Format> = self.resolve_format(path.as_ref(), variant)?;
+        self.make_internal(path.as_ref(), &T::default(), format, false)
+    }
+
+    /// Attempts to make a configuration file of type `variant` at `path` with the specified data from `data`.
+    /// If you want to overwrite an already existing file, you should use `make_override` instead.
+    /// This method can guess the variant based off the path if you specify `variant` as `None`,
+    /// and `variant` can also be any other `Format`, see `FormatChoice`.
+    ///
+    /// ```
+    /// let data: SomeBasicConfig = SomeBasicConfig {
+    ///     some_string: "Hello, world!".to_string(),
+    ///     some_bool: true,
+    ///     some_nest: SomeBasicNestedConfig {
+    ///         some_int: -4,
+    ///         some_float: 3.14_159_26,
+    ///         some_unsigned: 2_147_483_648,
+    ///     },
+    /// }
+    ///
+    /// let config: SomeBasicConfig = SomeBasicConfig::builder()
+    ///     .make_override("./config.toml", data, ConfigurationVariant::Toml)?
+    ///     .build()?;
+    /// ```
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - `path` is a directory
+    /// - The data was corrupt/malformed after the write
+    pub fn make_override<P: AsRef<Path> + ?Sized>(
+        self,
+        path: &P,
+        data: &T,
+        variant: impl Into<FormatChoice>,
+    ) -> Result<Self, Error> {
+        let format: Arc<dyn Format> = self.resolve_format(path.as_ref(), variant)?;
+        self.make_internal(path.as_ref(), data, format, true)
+    }
+
+    /// Attempts to make a configuration file of type `variant` at `path` with the default data for `T`.
+    /// If you want to overwrite an already existing file, you should use `make_default_override` instead.
+    /// This method can guess the variant based off the path if you specify `variant` as `None`,
+    /// and `variant` can also be any other `Format`, see `FormatChoice`.
+    ///
+    /// ```
+    /// let config: SomeBasicConfig = SomeBasicConfig::builder()
+    ///     .make_default_override("./config.toml", None)?
+    ///     .build()?;
+    /// ```
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - `path` is a directory
+    /// - The data was corrupt/malformed after the write
+    pub fn make_default_override<P: AsRef<Path> + ?Sized>(
+        self,
+        path: &P,
+        variant: impl Into<FormatChoice>,
+    ) -> Result<Self, Error>
+    where
+        T: Default,
+    {
+        let format: Arc<dyn Format> = self.resolve_format(path.as_ref(), variant)?;
+        self.make_internal(path.as_ref(), &T::default(), format, true)
+    }
+
+    /// Attempts to apply a JSON Merge Patch (RFC 7396) to the loaded configuration.
+    /// The patch is only kept if the result is still a valid `T`, otherwise the configuration is left as it was.
+    ///
+    /// ```
+    /// let mut builder = SomeBasicConfig::builder().use_file("./config.toml", ConfigurationVariant::Toml)?;
+    /// builder.apply_merge_patch(&serde_json::json!({ "some_nest": { "some_int": 4 } }))?;
+    /// let config: SomeBasicConfig = builder.build()?;
+    /// ```
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - A location (like `use_str`) hasn't been specified yet
+    /// - The patched configuration isn't a valid `T`
+    pub fn apply_merge_patch(&mut self, patch: &Document) -> Result<(), Error> {
+        self.patch_data(|document: &mut Document| {
+            crate::patch::merge_patch(document, patch);
+            Ok(())
+        })
+    }
+
+    /// Attempts to apply a JSON Patch (RFC 6902) to the loaded configuration.
+    /// The patch is only kept if every operation succeeds and the result is still a valid `T`,
+    /// otherwise the configuration is left as it was.
+    ///
+    /// ```
+    /// let mut builder = SomeBasicConfig::builder().use_file("./config.toml", ConfigurationVariant::Toml)?;
+    /// builder.apply_patch(&serde_json::json!([
+    ///     { "op": "replace", "path": "/some_nest/some_int", "value": 4 },
+    /// ]))?;
+    /// let config: SomeBasicConfig = builder.build()?;
+    /// ```
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - A location (like `use_str`) hasn't been specified yet
+    /// - An operation fails, see `cogwheel::patch::patch`
+    /// - The patched configuration isn't a valid `T`
+    pub fn apply_patch(&mut self, patch: &Document) -> Result<(), Error> {
+        self.patch_data(|document: &mut Document| crate::patch::patch(document, patch))
+    }
+
+    /// Runs `patch` on a copy of the loaded configuration as a `Document`, keeping the result if it's a valid `T`.
+    fn patch_data(
+        &mut self,
+        patch: impl FnOnce(&mut Document) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let data: &T = self.data.as_ref().ok_or(Error::NoConfigurationSpecified)?;
+        // `Secret`s have to be exposed, or they'd come back as their placeholder.
+        let mut document: Document = crate::secret::exposed(|| document::to_document(data))?;
+        patch(&mut document)?;
+
+        self.data = Some(serde_json::from_value(document)?);
+        Ok(())
+    }
+
+    /// Attempts to overwrite the file last read by a `use_file` or `make_*` method with `data`.
+    ///
+    /// ```
+    /// let mut config: SomeBasicConfig = builder.build_ref()?.clone();
+    /// config.some_bool = false;
+    ///
+    /// let builder: ConfigurationBuilder<SomeBasicConfig> = builder.save(&config)?;
+    /// ```
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - No file has been read yet
+    /// - The file can't be written to
+    /// - The data was corrupt/malformed after the write
+    pub fn save(self, data: &T) -> Result<Self, Error> {
+        let (path, format): (PathBuf, Arc<dyn Format>) =
+            self.file.clone().ok_or(Error::NoConfigurationSpecified)?;
+        self.make_internal(&path, data, format, true)
+    }
+
+    /// Attempts to read the file last read by a `use_file` or `make_*` method again,
+    /// with the same options (like `profile`) as before.
+    /// If the last thing read was a URL, it's fetched again like `use_url` instead.
+    ///
+    /// ```
+    /// let builder: ConfigurationBuilder<SomeBasicConfig> = builder.reload()?;
+    /// let config: &SomeBasicConfig = builder.build_ref()?;
+    /// ```
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - No file has been read yet
+    /// - The file can't be read anymore
+    /// - The file is no longer valid for `T`
+    pub fn reload(self) -> Result<Self, Error> {
+        #[cfg(feature = "url")]
+        if self.url.is_some() {
+            return self.reload_url();
+        }
+
+        let (path, format): (PathBuf, Arc<dyn Format>) =
+            self.file.clone().ok_or(Error::NoConfigurationSpecified)?;
+        self.use_file_as(&path, format)
     }
 
     fn gen_to_map<M: Serialize + for<'de> Deserialize<'de>>(
@@ -343,13 +1398,11 @@ impl<T: Serialize + for<'de> Deserialize<'de> + Configuration> ConfigurationBuil
         keys: Vec<String>,
         variant: ConfigurationVariant,
     ) -> Result<Self, Error> {
-        self.0.map_or_else(
+        self.data.map_or_else(
             || Err(Error::NoConfigurationSpecified),
             |mut original: T| {
-                let content: HashMap<&str, &dyn Any> = Self::gen_to_map(
-                    ConfigurationBuilder::<S>::gen_from_str(data, variant)?,
-                    variant,
-                )?;
+                let content: HashMap<&str, &dyn Any> =
+                    Self::gen_to_map(gen_from_str::<S>(data, variant)?, variant)?;
                 todo!()
             },
         )
@@ -364,3 +1417,718 @@ impl<T: Serialize + for<'de> Deserialize<'de> + Configuration> ConfigurationBuil
         todo!()
     }
 }
+
+/// How long `use_url` waits for a response before giving up.
+#[cfg(feature = "url")]
+const URL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// What's needed to fetch a URL again, see `ConfigurationBuilder::use_url`.
+#[cfg(feature = "url")]
+struct UrlState {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+#[cfg(feature = "url")]
+/// Methods that read configurations over HTTP(S).
+impl<T: Serialize + for<'de> Deserialize<'de> + Configuration> ConfigurationBuilder<T> {
+    /// Sets a file to keep a copy of whatever `use_url` fetches in,
+    /// which is used instead if the URL can't be fetched later.
+    /// Its format is guessed from its extension, or from its contents if it doesn't have one.
+    ///
+    /// ```
+    /// let config: SomeBasicConfig = SomeBasicConfig::builder()
+    ///     .cache_url_at("/var/cache/my_app/config.yaml")
+    ///     .use_url("https://config.internal/my_app/config.yaml")?
+    ///     .build()?;
+    /// ```
+    #[must_use]
+    pub fn cache_url_at<P: AsRef<Path> + ?Sized>(mut self, path: &P) -> Self {
+        self.url_cache = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Attempts to fetch `url` into a configuration struct, `T`.
+    /// The format is picked from the `Content-Type` (like `application/toml` or `application/x-yaml`),
+    /// then from the extension of the URL, then from the contents.
+    ///
+    /// `reload` fetches the URL again, sending the `ETag` and `Last-Modified` it got back,
+    /// so it only downloads and parses the configuration again if it changed.
+    /// If the URL can't be fetched (or takes over 30 seconds) and `cache_url_at` was set,
+    /// the cached copy is used with a `Warning`.
+    ///
+    /// ```
+    /// let mut builder = SomeBasicConfig::builder()
+    ///     .use_url("https://config.internal/my_app/config.yaml")?;
+    ///
+    /// loop {
+    ///     std::thread::sleep(Duration::from_secs(60));
+    ///     builder = builder.reload()?;
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - The URL can't be fetched (or responds with an error), and there's no cached copy
+    /// - The format can't be picked
+    /// - The configuration isn't valid, see `use_str`
+    pub fn use_url(mut self, url: &str) -> Result<Self, Error> {
+        self.root = None;
+        self.fetch_url(UrlState {
+            url: url.to_string(),
+            etag: None,
+            last_modified: None,
+        })
+    }
+
+    /// Fetches the URL from `use_url` again, see `reload`.
+    fn reload_url(mut self) -> Result<Self, Error> {
+        let state: UrlState = self.url.take().ok_or(Error::NoConfigurationSpecified)?;
+        self.fetch_url(state)
+    }
+
+    /// Fetches the URL in `state`, only asking for changes if something was loaded from it before.
+    fn fetch_url(mut self, mut state: UrlState) -> Result<Self, Error> {
+        let mut request = ureq::get(&state.url)
+            .config()
+            .timeout_global(Some(URL_TIMEOUT))
+            .build();
+        if self.data.is_some() {
+            if let Some(etag) = &state.etag {
+                request = request.header("If-None-Match", etag);
+            }
+            if let Some(last_modified) = &state.last_modified {
+                request = request.header("If-Modified-Since", last_modified);
+            }
+        }
+
+        let mut response = match request.call() {
+            Ok(response) => response,
+            Err(error) => return self.use_url_cache(state, error),
+        };
+        if response.status() == 304 {
+            self.url = Some(state);
+            return Ok(self);
+        }
+
+        let header = |name: &str| -> Option<String> {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        state.etag = header("etag");
+        state.last_modified = header("last-modified");
+        let content_type: Option<String> = header("content-type");
+        let output: Vec<u8> = response.body_mut().read_to_vec()?;
+
+        // Binary responses can't be sniffed, but can still be guessed from their `Content-Type`.
+        let data: Cow<'_, str> = decode(&output).unwrap_or_default();
+        let format: Arc<dyn Format> =
+            self.guess_url_format(&state.url, content_type.as_deref(), &data)?;
+        self.data = Some(self.load(&output, format.as_ref())?);
+        self.file = None;
+        self.url = Some(state);
+
+        if let Some(cache) = &self.url_cache {
+            std::fs::write(cache, &output)?;
+        }
+        Ok(self)
+    }
+
+    /// Loads the copy from `cache_url_at` after fetching `state` failed with `error`.
+    fn use_url_cache(mut self, state: UrlState, error: ureq::Error) -> Result<Self, Error> {
+        let Some(cache) = self.url_cache.clone() else {
+            return Err(error.into());
+        };
+        let Ok(output) = std::fs::read(&cache) else {
+            return Err(error.into());
+        };
+
+        self.warn(&Warning::UnreachableUrl {
+            url: state.url.clone(),
+            cache: cache.clone(),
+        });
+        let data: Cow<'_, str> = decode(&output).unwrap_or_default();
+        let format: Arc<dyn Format> = self
+            .guess_file_variant(&cache)
+            .ok()
+            .or_else(|| self.sniff_variant(&data))
+            .ok_or(Error::CouldNotGuess)?;
+        self.data = Some(self.load(&output, format.as_ref())?);
+        self.file = None;
+        self.url = Some(state);
+        Ok(self)
+    }
+
+    /// Picks the format of a response from its `Content-Type`, the extension of `url`, or `data`.
+    fn guess_url_format(
+        &self,
+        url: &str,
+        content_type: Option<&str>,
+        data: &str,
+    ) -> Result<Arc<dyn Format>, Error> {
+        // `application/x-yaml; charset=utf-8` and `application/vnd.my_app+json` are treated as extensions.
+        let subtype: Option<&str> = content_type
+            .and_then(|content_type: &str| content_type.split(';').next())
+            .and_then(|mime: &str| mime.trim().rsplit_once('/'))
+            .map(|(_, subtype): (&str, &str)| {
+                let subtype: &str = subtype.rsplit('+').next().unwrap_or(subtype);
+                subtype.strip_prefix("x-").unwrap_or(subtype)
+            });
+        if let Some(subtype) = subtype {
+            let subtype: String = subtype.to_lowercase();
+            if let Some(format) = self
+                .formats()
+                .find(|format: &Arc<dyn Format>| format.extensions().contains(&subtype.as_str()))
+            {
+                return Ok(format);
+            }
+        }
+
+        let path: &str = url.split(['?', '#']).next().unwrap_or(url);
+        self.guess_file_variant(Path::new(path))
+            .ok()
+            .or_else(|| self.sniff_variant(data))
+            .ok_or(Error::CouldNotGuess)
+    }
+}
+
+#[cfg(feature = "tokio")]
+/// Async counterparts to the methods that read or write files, using `tokio::fs`.
+impl<T: Serialize + for<'de> Deserialize<'de> + Configuration> ConfigurationBuilder<T> {
+    /// Async version of `use_file`, which also reads stdin if `path` is `-`.
+    ///
+    /// ```
+    /// let config: SomeBasicConfig = SomeBasicConfig::builder()
+    ///     .use_file_async("./config.toml", ConfigurationVariant::Toml)
+    ///     .await?
+    ///     .build()?;
+    /// ```
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - The file does not exist/is a directory
+    /// - The file can't be read
+    pub async fn use_file_async<S: AsRef<Path> + ?Sized>(
+        mut self,
+        path: &S,
+        variant: impl Format + 'static,
+    ) -> Result<Self, Error> {
+        self.root = None;
+        self.use_file_as_async(path.as_ref(), Arc::new(variant))
+            .await
+    }
+
+    async fn use_file_as_async(
+        mut self,
+        path: &Path,
+        format: Arc<dyn Format>,
+    ) -> Result<Self, Error> {
+        let mut output: Vec<u8> = Vec::new();
+        if path == Path::new(STDIN_PATH) {
+            tokio::io::AsyncReadExt::read_to_end(&mut tokio::io::stdin(), &mut output).await?;
+            self.data = Some(self.load(&output, format.as_ref())?);
+            self.file = None;
+            return Ok(self);
+        }
+        tokio::io::AsyncReadExt::read_to_end(&mut tokio::fs::File::open(path).await?, &mut output)
+            .await?;
+        #[cfg(feature = "compression")]
+        let output: Vec<u8> = decompress(path, output)?;
+
+        self.data = Some(self.load(&output, format.as_ref())?);
+        if let Some(document) = self.migrated.take() {
+            let output: Vec<u8> = self.gen_migrated(document, format.as_ref(), &output)?;
+            #[cfg(feature = "compression")]
+            let output: Vec<u8> = compress(path, output)?;
+            tokio::fs::write(path, output).await?;
+        }
+        self.file = Some((path.to_path_buf(), format));
+        Ok(self)
+    }
+
+    /// Writes `data` to `path`, failing if it already exists unless `overwrite` is set,
+    /// then reads it back like the `make_*` methods, unless that would lose any `Secret`s.
+    async fn make_internal_async(
+        self,
+        path: &Path,
+        data: &T,
+        format: Arc<dyn Format>,
+        overwrite: bool,
+    ) -> Result<Self, Error> {
+        let existing: Option<Vec<u8>> = match self.root {
+            Some(_) => match tokio::fs::read(path).await {
+                Ok(existing) => Some(existing),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+                Err(error) => return Err(error.into()),
+            },
+            None => None,
+        };
+        #[cfg(feature = "compression")]
+        let existing: Option<Vec<u8>> = existing
+            .map(|existing: Vec<u8>| decompress(path, existing))
+            .transpose()?;
+
+        let (output, redacted): (Result<Vec<u8>, Error>, bool) =
+            crate::secret::redacted(|| self.gen_to_vec(data, format.as_ref(), existing.as_deref()));
+        #[cfg(feature = "compression")]
+        let output: Result<Vec<u8>, Error> =
+            output.and_then(|output: Vec<u8>| compress(path, output));
+        let output: Vec<u8> = output?;
+        let mut file: tokio::fs::File = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create(overwrite)
+            .truncate(overwrite)
+            .create_new(!overwrite)
+            .open(path)
+            .await?;
+        tokio::io::AsyncWriteExt::write_all(&mut file, &output).await?;
+        tokio::io::AsyncWriteExt::flush(&mut file).await?;
+        if redacted {
+            return self.keep_made(path, data, format);
+        }
+        self.use_file_as_async(path, format).await
+    }
+
+    /// Async version of `make`.
+    ///
+    /// ```
+    /// let config: SomeBasicConfig = SomeBasicConfig::builder()
+    ///     .make_async("./config.toml", &data, None)
+    ///     .await?
+    ///     .build()?;
+    /// ```
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - The file already exists
+    /// - `path` is a directory
+    /// - The data was corrupt/malformed after the write
+    pub async fn make_async<P: AsRef<Path> + ?Sized>(
+        self,
+        path: &P,
+        data: &T,
+        variant: impl Into<FormatChoice>,
+    ) -> Result<Self, Error> {
+        let format: Arc<dyn Format> = self.resolve_format(path.as_ref(), variant)?;
+        self.make_internal_async(path.as_ref(), data, format, false)
+            .await
+    }
+
+    /// Async version of `make_default`.
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - The file already exists
+    /// - `path` is a directory
+    /// - The data was corrupt/malformed after the write
+    pub async fn make_default_async<P: AsRef<Path> + ?Sized>(
+        self,
+        path: &P,
+        variant: impl Into<FormatChoice>,
+    ) -> Result<Self, Error>
+    where
+        T: Default,
+    {
+        let format: Arc<dyn Format> = self.resolve_format(path.as_ref(), variant)?;
+        self.make_internal_async(path.as_ref(), &T::default(), format, false)
+            .await
+    }
+
+    /// Async version of `make_override`.
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - `path` is a directory
+    /// - The data was corrupt/malformed after the write
+    pub async fn make_override_async<P: AsRef<Path> + ?Sized>(
+        self,
+        path: &P,
+        data: &T,
+        variant: impl Into<FormatChoice>,
+    ) -> Result<Self, Error> {
+        let format: Arc<dyn Format> = self.resolve_format(path.as_ref(), variant)?;
+        self.make_internal_async(path.as_ref(), data, format, true)
+            .await
+    }
+
+    /// Async version of `make_default_override`.
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - `path` is a directory
+    /// - The data was corrupt/malformed after the write
+    pub async fn make_default_override_async<P: AsRef<Path> + ?Sized>(
+        self,
+        path: &P,
+        variant: impl Into<FormatChoice>,
+    ) -> Result<Self, Error>
+    where
+        T: Default,
+    {
+        let format: Arc<dyn Format> = self.resolve_format(path.as_ref(), variant)?;
+        self.make_internal_async(path.as_ref(), &T::default(), format, true)
+            .await
+    }
+
+    /// Async version of `save`.
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - No file has been read yet
+    /// - The file can't be written to
+    /// - The data was corrupt/malformed after the write
+    pub async fn save_async(self, data: &T) -> Result<Self, Error> {
+        let (path, format): (PathBuf, Arc<dyn Format>) =
+            self.file.clone().ok_or(Error::NoConfigurationSpecified)?;
+        self.make_internal_async(&path, data, format, true).await
+    }
+
+    /// Async version of `reload`.
+    /// A configuration from `use_url` is fetched again with the same blocking request as `reload`.
+    ///
+    /// ```
+    /// let builder: ConfigurationBuilder<SomeBasicConfig> = builder.reload_async().await?;
+    /// ```
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - No file or URL has been read yet
+    /// - The file can't be read anymore
+    /// - The file is no longer valid for `T`
+    pub async fn reload_async(self) -> Result<Self, Error> {
+        #[cfg(feature = "url")]
+        if self.url.is_some() {
+            return self.reload_url();
+        }
+
+        let (path, format): (PathBuf, Arc<dyn Format>) =
+            self.file.clone().ok_or(Error::NoConfigurationSpecified)?;
+        self.use_file_as_async(&path, format).await
+    }
+}
+
+/// Converts bytes into a `&str`, skipping byte order marks and converting UTF-16.
+pub(crate) fn decode(data: &[u8]) -> Result<Cow<'_, str>, Error> {
+    match data {
+        [0xFF, 0xFE, rest @ ..] => decode_utf16(rest, u16::from_le_bytes).map(Cow::Owned),
+        [0xFE, 0xFF, rest @ ..] => decode_utf16(rest, u16::from_be_bytes).map(Cow::Owned),
+        [0xEF, 0xBB, 0xBF, rest @ ..] | rest => std::str::from_utf8(rest)
+            .map(Cow::Borrowed)
+            .map_err(|_| Error::InvalidEncoding),
+    }
+}
+
+/// Reads and parses the file at `path`, decompressing it first if it's compressed.
+fn read_file(path: &Path, format: &dyn Format) -> Result<Document, Error> {
+    let data: Vec<u8> = fs::read(path)?;
+    #[cfg(feature = "compression")]
+    let data: Vec<u8> = decompress(path, data)?;
+    format.parse_bytes(&data)
+}
+
+/// A compression format that files can be read and written in, from their extension.
+#[cfg(feature = "compression")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    /// `.gz` files.
+    Gzip,
+    /// `.zst` files.
+    Zstd,
+}
+
+#[cfg(feature = "compression")]
+impl Compression {
+    /// The compression format of `path`, if it ends in `.gz` or `.zst`.
+    fn of(path: &Path) -> Option<Self> {
+        let extension: String = path.extension()?.to_string_lossy().to_lowercase();
+        match extension.as_str() {
+            "gz" => Some(Self::Gzip),
+            "zst" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// Decompresses `data` if `path` ends in `.gz` or `.zst`.
+#[cfg(feature = "compression")]
+pub(crate) fn decompress(path: &Path, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+    let mut output: Vec<u8> = Vec::new();
+    match Compression::of(path) {
+        Some(Compression::Gzip) => {
+            flate2::read::MultiGzDecoder::new(data.as_slice()).read_to_end(&mut output)?;
+        }
+        Some(Compression::Zstd) => output = zstd::decode_all(data.as_slice())?,
+        None => return Ok(data),
+    }
+    Ok(output)
+}
+
+/// Compresses `data` if `path` ends in `.gz` or `.zst`.
+#[cfg(feature = "compression")]
+fn compress(path: &Path, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+    match Compression::of(path) {
+        Some(Compression::Gzip) => {
+            let mut encoder: flate2::write::GzEncoder<Vec<u8>> =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&data)?;
+            Ok(encoder.finish()?)
+        }
+        Some(Compression::Zstd) => Ok(zstd::encode_all(data.as_slice(), 0)?),
+        None => Ok(data),
+    }
+}
+
+/// Converts UTF-16 bytes into a `String`, with `from_bytes` deciding the endianness.
+fn decode_utf16(data: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Result<String, Error> {
+    if data.len() % 2 != 0 {
+        return Err(Error::InvalidEncoding);
+    }
+
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|unit: &[u8]| from_bytes([unit[0], unit[1]]))
+        .collect();
+    String::from_utf16(&units).map_err(|_| Error::InvalidEncoding)
+}
+
+/// Converts anything serializable, like `T` or a `Document`, into `String`.
+pub(crate) fn gen_to_string<S: Serialize + ?Sized>(
+    data: &S,
+    variant: ConfigurationVariant,
+) -> Result<String, Error> {
+    match variant {
+        #[cfg(feature = "json")]
+        ConfigurationVariant::Json => Ok(serde_json::to_string_pretty(&data)?),
+        #[cfg(feature = "json5")]
+        ConfigurationVariant::Json5 => Ok(serde_json::to_string_pretty(&data)?),
+        #[cfg(feature = "toml")]
+        ConfigurationVariant::Toml => Ok(toml::to_string_pretty(&data)?),
+        #[cfg(feature = "yaml")]
+        ConfigurationVariant::Yaml => Ok(serde_yml::to_string(&data)?),
+        #[cfg(feature = "ron")]
+        ConfigurationVariant::Ron => Ok(ron::ser::to_string_pretty(
+            &data,
+            ron::ser::PrettyConfig::default(),
+        )?),
+        #[cfg(feature = "dotenv")]
+        ConfigurationVariant::Dotenv => crate::dotenv::to_string(&document::to_document(data)?),
+        #[cfg(feature = "properties")]
+        ConfigurationVariant::Properties => {
+            crate::properties::to_string(&document::to_document(data)?)
+        }
+        #[cfg(feature = "hcl")]
+        ConfigurationVariant::Hcl => match document::to_document(data)? {
+            Document::Object(map) => Ok(hcl::to_string(&hcl_body(&map)?)?),
+            document => Ok(hcl::to_string(&document)?),
+        },
+        #[cfg(feature = "kdl")]
+        ConfigurationVariant::Kdl => crate::kdl::to_string(&document::to_document(data)?),
+        #[cfg(feature = "xml")]
+        ConfigurationVariant::Xml => crate::xml::to_string(&document::to_document(data)?),
+        #[cfg(feature = "msgpack")]
+        ConfigurationVariant::Msgpack => Err(Error::BinaryFormat(variant.name().to_string())),
+        #[cfg(feature = "cbor")]
+        ConfigurationVariant::Cbor => Err(Error::BinaryFormat(variant.name().to_string())),
+    }
+}
+
+/// Converts anything serializable into bytes, which is its text unless `variant` is binary.
+pub(crate) fn gen_to_vec<S: Serialize + ?Sized>(
+    data: &S,
+    variant: ConfigurationVariant,
+) -> Result<Vec<u8>, Error> {
+    #[cfg(feature = "msgpack")]
+    if variant == ConfigurationVariant::Msgpack {
+        return Ok(rmp_serde::to_vec_named(data)?);
+    }
+    #[cfg(feature = "cbor")]
+    if variant == ConfigurationVariant::Cbor {
+        let mut output: Vec<u8> = Vec::new();
+        ciborium::into_writer(data, &mut output)?;
+        return Ok(output);
+    }
+
+    gen_to_string(data, variant).map(String::into_bytes)
+}
+
+/// Converts a table into an HCL body, writing tables as blocks instead of object attributes.
+/// Tables of tables (like `resource "a" "b" {}`) are written as labelled blocks,
+/// and tables with keys that can't be identifiers (like most maps) stay objects.
+#[cfg(feature = "hcl")]
+fn hcl_body(map: &document::Map<String, Document>) -> Result<hcl::Body, Error> {
+    let mut structures: Vec<hcl::Structure> = Vec::new();
+    for (key, value) in map {
+        let identifier: hcl::Identifier = hcl::Identifier::new(key.as_str()).map_err(|_| {
+            hcl::Error::Message(format!(
+                "`{key}` isn't an HCL identifier, so it can only be a key inside a table"
+            ))
+        })?;
+        match value {
+            Document::Object(table) if hcl_is_block(table) => {
+                hcl_blocks(&identifier, &mut Vec::new(), table, &mut structures)?;
+            }
+            value => {
+                structures.push(hcl::Attribute::new(identifier, hcl::to_expression(value)?).into());
+            }
+        }
+    }
+
+    Ok(structures.into_iter().collect())
+}
+
+/// Whether a table of tables becomes labels on its blocks.
+#[cfg(feature = "hcl")]
+fn hcl_is_labelled(table: &document::Map<String, Document>) -> bool {
+    !table.is_empty() && table.values().all(Document::is_object)
+}
+
+/// Whether a table can be written as (possibly labelled) blocks.
+#[cfg(feature = "hcl")]
+fn hcl_is_block(table: &document::Map<String, Document>) -> bool {
+    if hcl_is_labelled(table) {
+        table
+            .values()
+            .filter_map(Document::as_object)
+            .all(hcl_is_block)
+    } else {
+        table
+            .keys()
+            .all(|key: &String| hcl::Identifier::new(key.as_str()).is_ok())
+    }
+}
+
+/// Pushes the blocks for `table` onto `structures`, collecting the keys of tables of tables as labels.
+#[cfg(feature = "hcl")]
+fn hcl_blocks(
+    identifier: &hcl::Identifier,
+    labels: &mut Vec<String>,
+    table: &document::Map<String, Document>,
+    structures: &mut Vec<hcl::Structure>,
+) -> Result<(), Error> {
+    if hcl_is_labelled(table) {
+        for (label, value) in table {
+            if let Document::Object(value) = value {
+                labels.push(label.clone());
+                hcl_blocks(identifier, labels, value, structures)?;
+                labels.pop();
+            }
+        }
+    } else {
+        structures.push(
+            hcl::Block::builder(identifier.clone())
+                .add_labels(labels.iter().map(String::as_str))
+                .add_structures(hcl_body(table)?)
+                .build()
+                .into(),
+        );
+    }
+
+    Ok(())
+}
+
+/// Parses `&str` into any deserializable type, like `T` or a `Document`.
+pub(crate) fn gen_from_str<D: for<'de> Deserialize<'de>>(
+    data: &str,
+    variant: ConfigurationVariant,
+) -> Result<D, Error> {
+    match variant {
+        #[cfg(feature = "json")]
+        ConfigurationVariant::Json => Ok(serde_json::from_str::<D>(data)?),
+        #[cfg(feature = "json5")]
+        ConfigurationVariant::Json5 => Ok(json5::from_str::<D>(data)?),
+        #[cfg(feature = "toml")]
+        ConfigurationVariant::Toml => Ok(toml::from_str::<D>(data)?),
+        #[cfg(feature = "yaml")]
+        ConfigurationVariant::Yaml => Ok(serde_yml::from_str::<D>(data)?),
+        #[cfg(feature = "ron")]
+        // Files written from a `Document` (like migrated ones) have maps where `D` has structs.
+        ConfigurationVariant::Ron => ron::from_str::<D>(data).or_else(|error| {
+            ron_to_document(data)
+                .and_then(document::from_document)
+                .map_err(|_| error.into())
+        }),
+        #[cfg(feature = "dotenv")]
+        ConfigurationVariant::Dotenv => document::from_strings(crate::dotenv::from_str(data)?),
+        #[cfg(feature = "properties")]
+        ConfigurationVariant::Properties => {
+            document::from_strings(crate::properties::from_str(data)?)
+        }
+        #[cfg(feature = "hcl")]
+        ConfigurationVariant::Hcl => Ok(hcl::from_str::<D>(data)?),
+        #[cfg(feature = "kdl")]
+        ConfigurationVariant::Kdl => document::from_document(crate::kdl::from_str(data)?),
+        #[cfg(feature = "xml")]
+        ConfigurationVariant::Xml => document::from_strings(crate::xml::from_str(data)?),
+        #[cfg(feature = "msgpack")]
+        ConfigurationVariant::Msgpack => Ok(rmp_serde::from_slice::<D>(data.as_bytes())?),
+        #[cfg(feature = "cbor")]
+        ConfigurationVariant::Cbor => Ok(ciborium::from_reader::<D, _>(data.as_bytes())?),
+    }
+}
+
+/// Parses bytes into any deserializable type, decoding them as text unless `variant` is binary.
+pub(crate) fn gen_from_slice<D: for<'de> Deserialize<'de>>(
+    data: &[u8],
+    variant: ConfigurationVariant,
+) -> Result<D, Error> {
+    #[cfg(feature = "msgpack")]
+    if variant == ConfigurationVariant::Msgpack {
+        return Ok(rmp_serde::from_slice::<D>(data)?);
+    }
+    #[cfg(feature = "cbor")]
+    if variant == ConfigurationVariant::Cbor {
+        return Ok(ciborium::from_reader::<D, _>(data)?);
+    }
+
+    gen_from_str(&decode(data)?, variant)
+}
+
+/// Parses RON into a `Document`, with enum variants written like the other formats write them,
+/// so `Fast` becomes `"Fast"` and `Slow(3)` becomes `{ "Slow": 3 }`.
+/// Named structs below the top level, like `SomeNest(some_int: 4)`, become `{ "SomeNest": { "some_int": 4 } }`,
+/// which `document::from_document` reads as the struct.
+#[cfg(feature = "ron")]
+pub(crate) fn ron_to_document(data: &str) -> Result<Document, Error> {
+    use serde::de::{Deserializer, MapAccess, Visitor};
+
+    /// Reads the top level struct, whether it's named or not.
+    struct TableVisitor;
+
+    impl<'de> Visitor<'de> for TableVisitor {
+        type Value = document::Map<String, Document>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a struct or map")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut table: Self::Value = document::Map::new();
+            while let Some(key) = map.next_key::<String>()? {
+                let RonValue::Value(value) = map.next_value::<RonValue>()?;
+                table.insert(key, value);
+            }
+            Ok(table)
+        }
+    }
+
+    /// `ron` only keeps the names of enum variants when it's read into an untagged enum.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RonValue {
+        Value(Document),
+    }
+
+    let mut deserializer: ron::Deserializer = ron::Deserializer::from_str(data)?;
+    let table: document::Map<String, Document> = (&mut deserializer)
+        .deserialize_any(TableVisitor)
+        .and_then(|table: document::Map<String, Document>| {
+            deserializer.end()?;
+            Ok(table)
+        })
+        .map_err(|error: ron::Error| deserializer.span_error(error))?;
+    Ok(Document::Object(table))
+}
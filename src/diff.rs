@@ -0,0 +1,168 @@
+//! Structural differences between two configurations.
+
+use std::fmt::{self, Display};
+
+use crate::{
+    document::{self, Document},
+    Configuration, Error,
+};
+
+/// A value that changed between two configurations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    /// The dotted path of the value, like `some_nest.some_int`.
+    pub path: String,
+    /// The old value, or `None` if it was added.
+    pub old: Option<Document>,
+    /// The new value, or `None` if it was removed.
+    pub new: Option<Document>,
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(f, "~ {}: {old} -> {new}", self.path),
+            (None, Some(new)) => write!(f, "+ {}: {new}", self.path),
+            (Some(old), None) => write!(f, "- {}: {old}", self.path),
+            (None, None) => write!(f, "  {}", self.path),
+        }
+    }
+}
+
+/// Every value that changed between two configurations, in the order of their fields.
+///
+/// Displaying a `Diff` gives one `Change` per line, like:
+/// ```text
+/// ~ some_nest.some_int: -4 -> 4
+/// + some_nest.some_new: "Hello, world!"
+/// - some_old: true
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Diff {
+    /// Every change, in order.
+    pub changes: Vec<Change>,
+}
+
+impl Diff {
+    /// Whether nothing changed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, change) in self.changes.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds every value that changed from `old` to `new`.
+/// Tables are compared key by key, anything else (including arrays) is compared as a whole.
+///
+/// `Secret`s are compared by their actual values, but a changed `Secret` is shown with its placeholder.
+///
+/// ```
+/// let diff: Diff = cogwheel::diff(&old, &new)?;
+/// if !diff.is_empty() {
+///     println!("configuration changed:\n{diff}");
+/// }
+/// ```
+///
+/// # Errors
+/// This will fail if `old` or `new` can't be represented as a `Document`.
+pub fn diff<T: Configuration>(old: &T, new: &T) -> Result<Diff, Error> {
+    let mut changes: Vec<Change> = Vec::new();
+    diff_documents(
+        "",
+        &Sides {
+            old: &crate::secret::exposed(|| document::to_document(old))?,
+            new: &crate::secret::exposed(|| document::to_document(new))?,
+        },
+        &Sides {
+            old: &document::to_document(old)?,
+            new: &document::to_document(new)?,
+        },
+        &mut changes,
+    );
+
+    Ok(Diff { changes })
+}
+
+/// The old and new values at the same path.
+struct Sides<'a> {
+    old: &'a Document,
+    new: &'a Document,
+}
+
+impl<'a> Sides<'a> {
+    /// The values under `key`, staying at a value that isn't a table (like a `Secret`'s placeholder).
+    fn get(&self, key: &str) -> Sides<'a> {
+        let get = |document: &'a Document| -> &'a Document {
+            document
+                .as_object()
+                .and_then(|table| table.get(key))
+                .unwrap_or(document)
+        };
+        Sides {
+            old: get(self.old),
+            new: get(self.new),
+        }
+    }
+}
+
+/// Pushes every change from `values.old` to `values.new` under `path` onto `changes`.
+/// `shown` holds the same values with their `Secret`s redacted, which are what's put in a `Change`.
+fn diff_documents(path: &str, values: &Sides<'_>, shown: &Sides<'_>, changes: &mut Vec<Change>) {
+    let join = |key: &str| -> String {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{path}.{key}")
+        }
+    };
+
+    match (values.old, values.new) {
+        (Document::Object(old), Document::Object(new)) => {
+            for (key, old_value) in old {
+                match new.get(key) {
+                    Some(new_value) => diff_documents(
+                        &join(key),
+                        &Sides {
+                            old: old_value,
+                            new: new_value,
+                        },
+                        &shown.get(key),
+                        changes,
+                    ),
+                    None => changes.push(Change {
+                        path: join(key),
+                        old: Some(shown.get(key).old.clone()),
+                        new: None,
+                    }),
+                }
+            }
+            for key in new.keys() {
+                if !old.contains_key(key) {
+                    changes.push(Change {
+                        path: join(key),
+                        old: None,
+                        new: Some(shown.get(key).new.clone()),
+                    });
+                }
+            }
+        }
+        (old, new) if old != new => changes.push(Change {
+            path: path.to_string(),
+            old: Some(shown.old.clone()),
+            new: Some(shown.new.clone()),
+        }),
+        _ => {}
+    }
+}
@@ -0,0 +1,515 @@
+//! A format-agnostic representation of a configuration file.
+//!
+//! Every `ConfigurationVariant` can be parsed into a `Document`,
+//! which is what the builder works on when it has to change a file before it becomes `T`
+//! (like decrypting values or applying profiles).
+
+use serde::{
+    de::{
+        value::{MapDeserializer, SeqDeserializer},
+        DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, VariantAccess, Visitor,
+    },
+    forward_to_deserialize_any, Deserialize, Deserializer, Serialize,
+};
+pub use serde_json::Map;
+
+use crate::Error;
+
+/// The key that profiles are stored under, see `ConfigurationBuilder::profile`.
+pub const PROFILES_KEY: &str = "profile";
+
+/// A parsed configuration file, independent of the variant it came from.
+pub type Document = serde_json::Value;
+
+/// Converts anything serializable into a `Document`.
+///
+/// # Errors
+/// This will fail if `value` can't be represented as a `Document`, e.g. a map with non-string keys.
+pub fn to_document<S: Serialize + ?Sized>(value: &S) -> Result<Document, Error> {
+    Ok(serde_json::to_value(value)?)
+}
+
+/// Converts a `Document` into anything deserializable, like `T`.
+///
+/// ```
+/// let document: Document = serde_json::json!({ "some_string": "123", "some_int": -4 });
+/// let config: SomeConfig = from_document(document)?;
+///
+/// assert_eq!(config.some_string, "123");
+/// assert_eq!(config.some_int, -4);
+/// ```
+///
+/// # Errors
+/// This will fail if `document` isn't a valid `D`.
+pub fn from_document<D: DeserializeOwned>(document: Document) -> Result<D, Error> {
+    Ok(D::deserialize(DocumentDeserializer {
+        document,
+        strings: false,
+    })?)
+}
+
+/// Same as `from_document`, for `Document`s from formats that only have strings (like `.env` files).
+/// Strings are read as numbers or booleans wherever `D` expects one,
+/// and empty strings as empty tables or lists.
+///
+/// # Errors
+/// This will fail if `document` isn't a valid `D`.
+pub(crate) fn from_strings<D: DeserializeOwned>(document: Document) -> Result<D, Error> {
+    Ok(D::deserialize(DocumentDeserializer {
+        document,
+        strings: true,
+    })?)
+}
+
+/// Gets the value at a dotted path like `some_nest.some_int`, if there is one.
+///
+/// ```
+/// let document: Document = serde_json::json!({ "some_nest": { "some_int": -4 } });
+///
+/// assert_eq!(get_path(&document, "some_nest.some_int"), Some(&serde_json::json!(-4)));
+/// ```
+#[must_use]
+pub fn get_path<'a>(document: &'a Document, path: &str) -> Option<&'a Document> {
+    path.split('.')
+        .try_fold(document, |document: &Document, key: &str| document.get(key))
+}
+
+/// Removes the value at a dotted path like `some_nest.some_int`, if there is one.
+pub fn remove_path(document: &mut Document, path: &str) -> Option<Document> {
+    let (parent, key): (&mut Document, &str) = match path.rsplit_once('.') {
+        Some((parent, key)) => (path_mut(document, parent, false)?, key),
+        None => (document, path),
+    };
+    parent.as_object_mut()?.shift_remove(key)
+}
+
+/// Sets the value at a dotted path like `some_nest.some_int`, making any missing tables on the way.
+/// Anything on the way that isn't a table is replaced with one.
+pub fn insert_path(document: &mut Document, path: &str, value: Document) {
+    if let Some(target) = path_mut(document, path, true) {
+        *target = value;
+    }
+}
+
+/// Gets a mutable reference to the value at a dotted path, making missing tables if `create` is set.
+fn path_mut<'a>(document: &'a mut Document, path: &str, create: bool) -> Option<&'a mut Document> {
+    path.split('.')
+        .try_fold(document, |document: &mut Document, key: &str| {
+            if !create {
+                return document.get_mut(key);
+            }
+            if !document.is_object() {
+                *document = Document::Object(Map::new());
+            }
+            document
+                .as_object_mut()
+                .map(|map: &mut Map<String, Document>| map.entry(key).or_insert(Document::Null))
+        })
+}
+
+/// Calls `f` on every string in `document` and its JSON Pointer (like `/some_nest/some_list/0`),
+/// recursively, where `pointer` is the pointer to `document` itself.
+///
+/// # Errors
+/// This will fail if `f` fails.
+#[cfg(feature = "encryption")]
+pub(crate) fn try_for_each_string<E>(
+    document: &mut Document,
+    pointer: &mut String,
+    f: &mut impl FnMut(&str, &mut String) -> Result<(), E>,
+) -> Result<(), E> {
+    let length: usize = pointer.len();
+    match document {
+        Document::String(string) => f(pointer, string),
+        Document::Array(array) => {
+            array
+                .iter_mut()
+                .enumerate()
+                .try_for_each(|(index, value): (usize, &mut Document)| {
+                    pointer.push('/');
+                    pointer.push_str(&index.to_string());
+                    let result: Result<(), E> = try_for_each_string(value, pointer, f);
+                    pointer.truncate(length);
+                    result
+                })
+        }
+        Document::Object(map) => {
+            map.iter_mut()
+                .try_for_each(|(key, value): (&String, &mut Document)| {
+                    pointer.push('/');
+                    pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                    let result: Result<(), E> = try_for_each_string(value, pointer, f);
+                    pointer.truncate(length);
+                    result
+                })
+        }
+        Document::Null | Document::Bool(_) | Document::Number(_) => Ok(()),
+    }
+}
+
+/// Merges `overlay` into `base`.
+/// Tables are merged key by key, recursively, and anything else in `overlay` replaces what's in `base`.
+///
+/// ```
+/// let mut base: Document = serde_json::json!({ "a": 1, "nest": { "b": 2, "c": 3 } });
+/// merge(&mut base, serde_json::json!({ "nest": { "c": 4 } }));
+///
+/// assert_eq!(base, serde_json::json!({ "a": 1, "nest": { "b": 2, "c": 4 } }));
+/// ```
+pub fn merge(base: &mut Document, overlay: Document) {
+    match (base, overlay) {
+        (Document::Object(base), Document::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Removes every profile from `document`, then merges the one called `profile` over what's left.
+///
+/// # Errors
+/// This will fail if `document` has no profile called `profile`.
+pub(crate) fn apply_profile(document: &mut Document, profile: &str) -> Result<(), Error> {
+    let overlay: Document = document
+        .as_object_mut()
+        .and_then(|map| map.shift_remove(PROFILES_KEY))
+        .and_then(|mut profiles| profiles.as_object_mut()?.shift_remove(profile))
+        .ok_or_else(|| Error::UnknownProfile(profile.to_string()))?;
+
+    merge(document, overlay);
+    Ok(())
+}
+
+/// Deserializes a `Document` like `serde_json::Value` does,
+/// but with enums and named structs from RON, and strings read as other values if `strings` is set.
+struct DocumentDeserializer {
+    document: Document,
+    strings: bool,
+}
+
+impl DocumentDeserializer {
+    /// Reads an empty string as `empty`, since that's how formats like XML write empty tables and lists.
+    fn or_empty(self, empty: impl FnOnce() -> Document) -> Self {
+        match self.document {
+            Document::String(string) if self.strings && string.is_empty() => Self {
+                document: empty(),
+                strings: self.strings,
+            },
+            document => Self {
+                document,
+                strings: self.strings,
+            },
+        }
+    }
+
+    /// Takes a struct out of a table keyed by its `name` (that isn't one of its `fields`),
+    /// which is how RON's named structs like `SomeNest(some_int: 4)` are parsed,
+    /// with anything merged next to it (like defaults) under it.
+    fn without_name(self, name: &str, fields: &[&str]) -> Self {
+        match self.document {
+            Document::Object(mut map)
+                if !fields.contains(&name)
+                    && map.contains_key(name)
+                    && map
+                        .keys()
+                        .all(|key: &String| key == name || fields.contains(&key.as_str())) =>
+            {
+                let named: Document = map.shift_remove(name).unwrap_or_default();
+                let mut document: Document = Document::Object(map);
+                merge(&mut document, named);
+                Self {
+                    document,
+                    strings: self.strings,
+                }
+            }
+            document => Self {
+                document,
+                strings: self.strings,
+            },
+        }
+    }
+
+    /// Reads a string as an integer if it is one, or the `Document` as it is otherwise.
+    fn deserialize_integer<'de, V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, serde_json::Error> {
+        if let (true, Document::String(string)) = (self.strings, &self.document) {
+            if let Ok(integer) = string.parse::<i64>() {
+                return visitor.visit_i64(integer);
+            }
+            if let Ok(unsigned) = string.parse::<u64>() {
+                return visitor.visit_u64(unsigned);
+            }
+        }
+        self.deserialize_any(visitor)
+    }
+
+    /// Reads a string as a float if it is one, or the `Document` as it is otherwise.
+    fn deserialize_float<'de, V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, serde_json::Error> {
+        if let (true, Document::String(string)) = (self.strings, &self.document) {
+            if let Ok(float) = string.parse::<f64>() {
+                return visitor.visit_f64(float);
+            }
+        }
+        self.deserialize_any(visitor)
+    }
+}
+
+impl IntoDeserializer<'_, serde_json::Error> for DocumentDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for DocumentDeserializer {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let strings: bool = self.strings;
+        let child = move |document: Document| DocumentDeserializer { document, strings };
+        match self.document {
+            Document::Null => visitor.visit_unit(),
+            Document::Bool(boolean) => visitor.visit_bool(boolean),
+            Document::Number(number) => number.deserialize_any(visitor),
+            Document::String(string) => visitor.visit_string(string),
+            Document::Array(array) => {
+                let mut seq = SeqDeserializer::new(array.into_iter().map(child));
+                let value: V::Value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Document::Object(map) => {
+                let mut map = MapDeserializer::new(
+                    map.into_iter()
+                        .map(|(key, value): (String, Document)| (key, child(value))),
+                );
+                let value: V::Value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        if let (true, Document::String(string)) = (self.strings, &self.document) {
+            if let Ok(boolean) = string.parse::<bool>() {
+                return visitor.visit_bool(boolean);
+            }
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_float(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_float(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.document {
+            Document::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.or_empty(|| Document::Array(Vec::new()))
+            .deserialize_any(visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.or_empty(|| Document::Object(Map::new()))
+            .deserialize_any(visitor)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.document {
+            Document::String(string) if string == name => visitor.visit_unit(),
+            document => Self {
+                document,
+                strings: self.strings,
+            }
+            .deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self.without_name(name, &[]))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.without_name(name, &[]).deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.or_empty(|| Document::Object(Map::new()))
+            .without_name(name, fields)
+            .deserialize_any(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.document {
+            // A unit variant, like `"Fast"`
+            Document::String(variant) => visitor.visit_enum(
+                IntoDeserializer::<serde_json::Error>::into_deserializer(variant),
+            ),
+            // Any other variant, like `{ "Slow": 3 }`
+            Document::Object(map) if map.len() == 1 => {
+                let Some((variant, value)) = map.into_iter().next() else {
+                    unreachable!("the map has one entry");
+                };
+                visitor.visit_enum(VariantDeserializer(
+                    variant,
+                    Self {
+                        document: value,
+                        strings: self.strings,
+                    },
+                ))
+            }
+            document => Self {
+                document,
+                strings: self.strings,
+            }
+            .deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        char str string bytes byte_buf unit identifier ignored_any
+    }
+}
+
+/// An enum variant's name and its content, from a table like `{ "Slow": 3 }`.
+struct VariantDeserializer(String, DocumentDeserializer);
+
+impl<'de> EnumAccess<'de> for VariantDeserializer {
+    type Error = serde_json::Error;
+    type Variant = DocumentDeserializer;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<(S::Value, Self::Variant), Self::Error> {
+        let variant: S::Value = seed.deserialize(
+            IntoDeserializer::<serde_json::Error>::into_deserializer(self.0),
+        )?;
+        Ok((variant, self.1))
+    }
+}
+
+impl<'de> VariantAccess<'de> for DocumentDeserializer {
+    type Error = serde_json::Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        <()>::deserialize(self)
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<S::Value, Self::Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+}
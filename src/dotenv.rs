@@ -0,0 +1,165 @@
+//! Reading and writing `.env` files.
+//!
+//! Each line is a `KEY=value`, optionally starting with `export`, and `#` starts a comment.
+//! Keys are lowercased and `__` separates nested keys, so `SOME_NEST__SOME_INT=4` sets `some_nest.some_int`.
+//!
+//! Values can be:
+//! - Unquoted, ending at a ` #` comment
+//! - Single quoted, which are kept as they are and can span several lines
+//! - Double quoted, which can span several lines and have `\n`, `\r`, `\t`, `\"`, `\\` and `\$` escapes
+//!
+//! Every value is a string, which is read as a number or boolean where `T` has one.
+
+use crate::{
+    document::{self, Document, Map},
+    Error,
+};
+
+/// Parses a `.env` file into a `Document`.
+///
+/// # Errors
+/// This will fail if a line isn't a `KEY=value`, or a quote is never closed.
+pub fn from_str(data: &str) -> Result<Document, Error> {
+    let mut document: Document = Document::Object(Map::new());
+    let mut lines = data.lines().enumerate();
+
+    while let Some((index, line)) = lines.next() {
+        let line: &str = line.trim_start();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line: &str = line.strip_prefix("export ").map_or(line, str::trim_start);
+        let (key, value): (&str, &str) = line
+            .split_once('=')
+            .ok_or(Error::InvalidDotenv(index + 1))?;
+        let key: &str = key.trim();
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        {
+            return Err(Error::InvalidDotenv(index + 1));
+        }
+
+        let value: &str = value.trim_start();
+        let value: Document = if let Some(quote @ ('"' | '\'')) = value.chars().next() {
+            let mut quoted: String = value[1..].to_string();
+            let end: usize = loop {
+                if let Some(end) = find_closing_quote(&quoted, quote) {
+                    break end;
+                }
+                let (_, next): (usize, &str) =
+                    lines.next().ok_or(Error::InvalidDotenv(index + 1))?;
+                quoted.push('\n');
+                quoted.push_str(next);
+            };
+            quoted.truncate(end);
+
+            if quote == '"' {
+                unescape(&quoted).into()
+            } else {
+                quoted.into()
+            }
+        } else {
+            let value: &str = value.split_once(" #").map_or(value, |(value, _)| value);
+            value.trim_end().into()
+        };
+
+        let path: String = key.to_lowercase().replace("__", ".");
+        document::insert_path(&mut document, &path, value);
+    }
+
+    Ok(document)
+}
+
+/// Finds the index of the `quote` that closes `data`, skipping escaped ones in double quotes.
+fn find_closing_quote(data: &str, quote: char) -> Option<usize> {
+    let mut escaped: bool = false;
+    for (index, c) in data.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quote == '"' => escaped = true,
+            c if c == quote => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Replaces the escapes in a double quoted value.
+fn unescape(data: &str) -> String {
+    let mut output: String = String::with_capacity(data.len());
+    let mut chars = data.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => output.push('\n'),
+            Some('r') => output.push('\r'),
+            Some('t') => output.push('\t'),
+            Some(c) => output.push(c),
+            None => output.push('\\'),
+        }
+    }
+    output
+}
+
+/// Converts a `Document` into a `.env` file, with nested keys joined by `__`.
+/// Strings are always double quoted, and `null`s are left out.
+///
+/// # Errors
+/// This will fail if `document` isn't a table, or has an array in it.
+pub fn to_string(document: &Document) -> Result<String, Error> {
+    let mut output: String = String::new();
+    write_table("", document, &mut output)?;
+    Ok(output)
+}
+
+/// Writes every value in `document` to `output`, with their keys starting with `prefix`.
+fn write_table(prefix: &str, document: &Document, output: &mut String) -> Result<(), Error> {
+    let Document::Object(map) = document else {
+        return Err(Error::UnsupportedDotenvValue(prefix.to_string()));
+    };
+
+    for (key, value) in map {
+        let key: String = format!("{prefix}{}", key.to_uppercase());
+        let value: String = match value {
+            Document::Null => continue,
+            Document::Bool(boolean) => boolean.to_string(),
+            Document::Number(number) => number.to_string(),
+            Document::String(string) => format!("\"{}\"", escape(string)),
+            Document::Object(_) => {
+                write_table(&format!("{key}__"), value, output)?;
+                continue;
+            }
+            Document::Array(_) => return Err(Error::UnsupportedDotenvValue(key)),
+        };
+
+        output.push_str(&key);
+        output.push('=');
+        output.push_str(&value);
+        output.push('\n');
+    }
+    Ok(())
+}
+
+/// Escapes a value so it can be double quoted.
+fn escape(data: &str) -> String {
+    let mut output: String = String::with_capacity(data.len());
+    for c in data.chars() {
+        match c {
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            '"' | '\\' | '$' => {
+                output.push('\\');
+                output.push(c);
+            }
+            c => output.push(c),
+        }
+    }
+    output
+}
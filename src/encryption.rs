@@ -0,0 +1,150 @@
+//! Encrypted values inside of configuration files.
+//!
+//! An encrypted value is a string like `enc:v1:<base64>`,
+//! where the base64 is a 12 byte nonce followed by the ChaCha20-Poly1305 ciphertext.
+//! Values can be encrypted with `Key::encrypt` (or the `encrypt` example),
+//! and are decrypted when loading with `ConfigurationBuilder::decrypt_with`:
+//! ```
+//! // password = "enc:v1:..."
+//! let config: DatabaseConfig = DatabaseConfig::builder()
+//!     .decrypt_with(Key::from_env("COGWHEEL_KEY")?)
+//!     .use_file("./database.toml", ConfigurationVariant::Toml)?
+//!     .build()?;
+//! ```
+
+use std::{env, fmt, fs, path::Path};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Nonce,
+};
+use zeroize::Zeroizing;
+
+use crate::{document::Document, Error};
+
+/// The prefix of every encrypted value.
+pub const PREFIX: &str = "enc:v1:";
+
+const NONCE_LENGTH: usize = 12;
+
+/// A key used to encrypt and decrypt values.
+///
+/// Keys are stored as 32 bytes of base64, either in a file or in an environment variable.
+/// The key is zeroed when dropped.
+pub struct Key(Zeroizing<[u8; 32]>);
+
+impl Key {
+    /// Generates a new random key.
+    #[must_use]
+    pub fn generate() -> Self {
+        Self(Zeroizing::new(
+            ChaCha20Poly1305::generate_key(&mut OsRng).into(),
+        ))
+    }
+
+    /// Reads a key from base64.
+    ///
+    /// # Errors
+    /// This will fail if `data` isn't base64 for exactly 32 bytes.
+    pub fn from_base64(data: &str) -> Result<Self, Error> {
+        let bytes: Zeroizing<Vec<u8>> = Zeroizing::new(
+            STANDARD
+                .decode(data.trim())
+                .map_err(|_| Error::InvalidKey)?,
+        );
+        let key: [u8; 32] = bytes.as_slice().try_into().map_err(|_| Error::InvalidKey)?;
+
+        Ok(Self(Zeroizing::new(key)))
+    }
+
+    /// Reads a key from a file containing its base64.
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - The file can't be read
+    /// - The file isn't base64 for exactly 32 bytes
+    pub fn from_file<P: AsRef<Path> + ?Sized>(path: &P) -> Result<Self, Error> {
+        Self::from_base64(&Zeroizing::new(fs::read_to_string(path)?))
+    }
+
+    /// Reads a key from an environment variable containing its base64.
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - The environment variable isn't set
+    /// - The variable isn't base64 for exactly 32 bytes
+    pub fn from_env(name: &str) -> Result<Self, Error> {
+        Self::from_base64(&Zeroizing::new(
+            env::var(name).map_err(|_| Error::MissingEnvironmentVariable(name.to_string()))?,
+        ))
+    }
+
+    /// Converts this key to base64, for storing it in a file or environment variable.
+    #[must_use]
+    pub fn to_base64(&self) -> String {
+        STANDARD.encode(self.0.as_slice())
+    }
+
+    /// Encrypts `value` into a string like `enc:v1:<base64>`.
+    ///
+    /// # Errors
+    /// This will fail if encryption fails, which shouldn't happen.
+    pub fn encrypt(&self, value: &str) -> Result<String, Error> {
+        let cipher: ChaCha20Poly1305 = ChaCha20Poly1305::new(self.0.as_slice().into());
+        let nonce: Nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let mut output: Vec<u8> = nonce.to_vec();
+        output.extend(
+            cipher
+                .encrypt(&nonce, value.as_bytes())
+                .map_err(|_| Error::EncryptionError)?,
+        );
+
+        Ok(format!("{PREFIX}{}", STANDARD.encode(output)))
+    }
+
+    /// Decrypts a string made by `encrypt`.
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - `value` doesn't start with `enc:v1:`
+    /// - `value` was encrypted with another key, or was tampered with
+    pub fn decrypt(&self, value: &str) -> Result<String, Error> {
+        let data: Vec<u8> = value
+            .strip_prefix(PREFIX)
+            .and_then(|data: &str| STANDARD.decode(data).ok())
+            .filter(|data: &Vec<u8>| data.len() >= NONCE_LENGTH)
+            .ok_or(Error::DecryptionError)?;
+        let (nonce, ciphertext): (&[u8], &[u8]) = data.split_at(NONCE_LENGTH);
+        let cipher: ChaCha20Poly1305 = ChaCha20Poly1305::new(self.0.as_slice().into());
+        let plaintext: Vec<u8> = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| Error::DecryptionError)?;
+
+        String::from_utf8(plaintext).map_err(|_| Error::DecryptionError)
+    }
+
+    /// Decrypts every encrypted string in `document`, leaving everything else as is,
+    /// and returns the JSON Pointers of the strings that were decrypted.
+    pub(crate) fn decrypt_document(&self, document: &mut Document) -> Result<Vec<String>, Error> {
+        let mut decrypted: Vec<String> = Vec::new();
+        crate::document::try_for_each_string(
+            document,
+            &mut String::new(),
+            &mut |pointer: &str, value: &mut String| -> Result<(), Error> {
+                if value.starts_with(PREFIX) {
+                    *value = self.decrypt(value)?;
+                    decrypted.push(pointer.to_string());
+                }
+                Ok(())
+            },
+        )?;
+        Ok(decrypted)
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Key([REDACTED])")
+    }
+}
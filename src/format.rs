@@ -0,0 +1,335 @@
+//! Configuration file formats.
+//!
+//! The built-in formats are the variants of `ConfigurationVariant`,
+//! and anything else can be added by implementing `Format` and registering it
+//! with `ConfigurationBuilder::register_format`.
+
+use std::sync::Arc;
+
+use crate::{config::ConfigurationVariant, document::Document, Error};
+
+/// A configuration file format, which converts between text (or bytes) and a `Document`.
+///
+/// ```
+/// /// `key=value` lines, for flat configurations.
+/// struct KeyValue;
+///
+/// impl Format for KeyValue {
+///     fn name(&self) -> &str {
+///         "key-value"
+///     }
+///
+///     fn extensions(&self) -> &[&str] {
+///         &["kv"]
+///     }
+///
+///     fn parse(&self, data: &str) -> Result<Document, Error> {
+///         let map: Map<String, Document> = data
+///             .lines()
+///             .filter_map(|line: &str| line.split_once('='))
+///             .map(|(key, value)| (key.trim().to_string(), value.trim().into()))
+///             .collect();
+///         Ok(Document::Object(map))
+///     }
+///
+///     fn serialize(&self, document: &Document) -> Result<String, Error> {
+///         ...
+///     }
+/// }
+///
+/// let config: SomeFlatConfig = SomeFlatConfig::builder()
+///     .register_format(KeyValue)
+///     .make_default("./config.kv", None)?
+///     .build()?;
+/// ```
+pub trait Format: Send + Sync {
+    /// The name of this format, used in errors, like `TOML`.
+    fn name(&self) -> &str;
+
+    /// The file extensions of this format, lowercase and without the dot, like `["yaml", "yml"]`.
+    fn extensions(&self) -> &[&str];
+
+    /// Whether `data` looks like this format, for when there's no extension to go by.
+    /// This only has to be a good guess, and never matches by default.
+    fn sniff(&self, _data: &str) -> bool {
+        false
+    }
+
+    /// Whether every value this format parses is a string, like in `.env` files,
+    /// which makes them read as numbers or booleans where `T` has them. This is `false` by default.
+    fn only_strings(&self) -> bool {
+        false
+    }
+
+    /// Parses `data` into a `Document`.
+    ///
+    /// # Errors
+    /// This should fail if `data` is malformed.
+    fn parse(&self, data: &str) -> Result<Document, Error>;
+
+    /// Converts `document` into text.
+    ///
+    /// # Errors
+    /// This should fail if `document` can't be represented in this format.
+    fn serialize(&self, document: &Document) -> Result<String, Error>;
+
+    /// Parses bytes into a `Document`, which decodes them as text for `parse` by default.
+    /// Binary formats should implement this (and `serialize_bytes`) instead.
+    ///
+    /// # Errors
+    /// This should fail if `data` is malformed.
+    fn parse_bytes(&self, data: &[u8]) -> Result<Document, Error> {
+        self.parse(&crate::config::decode(data)?)
+    }
+
+    /// Converts `document` into bytes, which is `serialize` as UTF-8 by default.
+    ///
+    /// # Errors
+    /// This should fail if `document` can't be represented in this format.
+    fn serialize_bytes(&self, document: &Document) -> Result<Vec<u8>, Error> {
+        self.serialize(document).map(String::into_bytes)
+    }
+
+    /// The built-in variant this is, which lets `T` skip the `Document` when nothing has to change it.
+    #[doc(hidden)]
+    fn variant(&self) -> Option<ConfigurationVariant> {
+        None
+    }
+}
+
+impl Format for ConfigurationVariant {
+    fn name(&self) -> &str {
+        match *self {
+            #[cfg(feature = "json")]
+            Self::Json => "JSON",
+            #[cfg(feature = "json5")]
+            Self::Json5 => "JSON5",
+            #[cfg(feature = "toml")]
+            Self::Toml => "TOML",
+            #[cfg(feature = "yaml")]
+            Self::Yaml => "YAML",
+            #[cfg(feature = "ron")]
+            Self::Ron => "RON",
+            #[cfg(feature = "dotenv")]
+            Self::Dotenv => ".env",
+            #[cfg(feature = "properties")]
+            Self::Properties => "Java properties",
+            #[cfg(feature = "hcl")]
+            Self::Hcl => "HCL",
+            #[cfg(feature = "kdl")]
+            Self::Kdl => "KDL",
+            #[cfg(feature = "xml")]
+            Self::Xml => "XML",
+            #[cfg(feature = "msgpack")]
+            Self::Msgpack => "MessagePack",
+            #[cfg(feature = "cbor")]
+            Self::Cbor => "CBOR",
+        }
+    }
+
+    fn extensions(&self) -> &[&str] {
+        match *self {
+            #[cfg(feature = "json")]
+            Self::Json => &["json"],
+            #[cfg(feature = "json5")]
+            Self::Json5 => &["json5", "jsonc"],
+            #[cfg(feature = "toml")]
+            Self::Toml => &["toml"],
+            #[cfg(feature = "yaml")]
+            Self::Yaml => &["yaml", "yml"],
+            #[cfg(feature = "ron")]
+            Self::Ron => &["ron"],
+            #[cfg(feature = "dotenv")]
+            Self::Dotenv => &["env"],
+            #[cfg(feature = "properties")]
+            Self::Properties => &["properties"],
+            #[cfg(feature = "hcl")]
+            Self::Hcl => &["hcl"],
+            #[cfg(feature = "kdl")]
+            Self::Kdl => &["kdl"],
+            #[cfg(feature = "xml")]
+            Self::Xml => &["xml"],
+            #[cfg(feature = "msgpack")]
+            Self::Msgpack => &["msgpack", "mpk"],
+            #[cfg(feature = "cbor")]
+            Self::Cbor => &["cbor"],
+        }
+    }
+
+    fn sniff(&self, data: &str) -> bool {
+        let Some(line) = data
+            .lines()
+            .map(str::trim)
+            .find(|line: &&str| !line.is_empty() && !line.starts_with('#'))
+        else {
+            return false;
+        };
+
+        match *self {
+            #[cfg(feature = "json")]
+            Self::Json => line.starts_with('{'),
+            #[cfg(feature = "json5")]
+            Self::Json5 => {
+                line.starts_with('{') || line.starts_with("//") || line.starts_with("/*")
+            }
+            #[cfg(feature = "toml")]
+            Self::Toml => {
+                (line.starts_with('[') && line.ends_with(']'))
+                    || line
+                        .find('=')
+                        .is_some_and(|index: usize| !line[..index].contains(':'))
+            }
+            #[cfg(feature = "yaml")]
+            Self::Yaml => line.starts_with("---") || line.starts_with("- ") || line.contains(':'),
+            #[cfg(feature = "ron")]
+            Self::Ron => line.starts_with('('),
+            // Most lines look like TOML too, but `!` comments and continuations don't.
+            #[cfg(feature = "properties")]
+            Self::Properties => line.starts_with('!') || line.ends_with('\\'),
+            // A labelled block or object attribute, like `server "a" {` or `some_nest = {`,
+            // since unlabelled blocks look like KDL.
+            #[cfg(feature = "hcl")]
+            Self::Hcl => line.ends_with('{') && !line.starts_with('{') && line.contains(['"', '=']),
+            // A node opening its children, like `some_nest {`.
+            #[cfg(feature = "kdl")]
+            Self::Kdl => {
+                line.ends_with('{') && !line.starts_with('{') && !line.contains([':', '='])
+            }
+            #[cfg(feature = "xml")]
+            Self::Xml => line.starts_with('<'),
+            // Binary formats are never sniffed from text.
+            #[cfg(feature = "msgpack")]
+            Self::Msgpack => false,
+            #[cfg(feature = "cbor")]
+            Self::Cbor => false,
+            #[cfg(feature = "dotenv")]
+            Self::Dotenv => line
+                .strip_prefix("export ")
+                .unwrap_or(line)
+                .split_once('=')
+                .is_some_and(|(key, _): (&str, &str)| {
+                    !key.is_empty()
+                        && key
+                            .chars()
+                            .all(|c: char| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+                }),
+        }
+    }
+
+    fn only_strings(&self) -> bool {
+        match *self {
+            #[cfg(feature = "json")]
+            Self::Json => false,
+            #[cfg(feature = "json5")]
+            Self::Json5 => false,
+            #[cfg(feature = "toml")]
+            Self::Toml => false,
+            #[cfg(feature = "yaml")]
+            Self::Yaml => false,
+            #[cfg(feature = "ron")]
+            Self::Ron => false,
+            #[cfg(feature = "dotenv")]
+            Self::Dotenv => true,
+            #[cfg(feature = "properties")]
+            Self::Properties => true,
+            #[cfg(feature = "hcl")]
+            Self::Hcl => false,
+            #[cfg(feature = "kdl")]
+            Self::Kdl => false,
+            #[cfg(feature = "xml")]
+            Self::Xml => true,
+            #[cfg(feature = "msgpack")]
+            Self::Msgpack => false,
+            #[cfg(feature = "cbor")]
+            Self::Cbor => false,
+        }
+    }
+
+    fn parse(&self, data: &str) -> Result<Document, Error> {
+        #[cfg(feature = "ron")]
+        if *self == Self::Ron {
+            return crate::config::ron_to_document(data);
+        }
+        crate::config::gen_from_str(data, *self)
+    }
+
+    fn serialize(&self, document: &Document) -> Result<String, Error> {
+        crate::config::gen_to_string(document, *self)
+    }
+
+    fn parse_bytes(&self, data: &[u8]) -> Result<Document, Error> {
+        #[cfg(feature = "ron")]
+        if *self == Self::Ron {
+            return self.parse(&crate::config::decode(data)?);
+        }
+        crate::config::gen_from_slice(data, *self)
+    }
+
+    fn serialize_bytes(&self, document: &Document) -> Result<Vec<u8>, Error> {
+        crate::config::gen_to_vec(document, *self)
+    }
+
+    fn variant(&self) -> Option<ConfigurationVariant> {
+        Some(*self)
+    }
+}
+
+impl<F: Format + ?Sized> Format for &F {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn extensions(&self) -> &[&str] {
+        (**self).extensions()
+    }
+
+    fn sniff(&self, data: &str) -> bool {
+        (**self).sniff(data)
+    }
+
+    fn only_strings(&self) -> bool {
+        (**self).only_strings()
+    }
+
+    fn parse(&self, data: &str) -> Result<Document, Error> {
+        (**self).parse(data)
+    }
+
+    fn serialize(&self, document: &Document) -> Result<String, Error> {
+        (**self).serialize(document)
+    }
+
+    fn parse_bytes(&self, data: &[u8]) -> Result<Document, Error> {
+        (**self).parse_bytes(data)
+    }
+
+    fn serialize_bytes(&self, document: &Document) -> Result<Vec<u8>, Error> {
+        (**self).serialize_bytes(document)
+    }
+
+    fn variant(&self) -> Option<ConfigurationVariant> {
+        (**self).variant()
+    }
+}
+
+/// The format a `make_*` method writes, which is any `Format` (like one given to `register_format`),
+/// or an `Option<ConfigurationVariant>` where `None` guesses it from the path.
+///
+/// ```
+/// let config: SomeFlatConfig = SomeFlatConfig::builder()
+///     .make_default("./config.txt", KeyValue)?
+///     .build()?;
+/// ```
+pub struct FormatChoice(pub(crate) Option<Arc<dyn Format>>);
+
+impl From<Option<ConfigurationVariant>> for FormatChoice {
+    fn from(variant: Option<ConfigurationVariant>) -> Self {
+        Self(variant.map(|variant: ConfigurationVariant| Arc::new(variant) as Arc<dyn Format>))
+    }
+}
+
+impl<F: Format + 'static> From<F> for FormatChoice {
+    fn from(format: F) -> Self {
+        Self(Some(Arc::new(format)))
+    }
+}
@@ -0,0 +1,659 @@
+//! Reading and writing [KDL](https://kdl.dev) files.
+//!
+//! Both KDL 1 and KDL 2 can be read, and KDL 2 is written.
+//! Nodes map onto `T` like this:
+//! - The nodes in a file (or in a node's children) are a table, keyed by node name
+//! - A node with one argument is that value, like `some_int 4`
+//! - A node with several arguments is a list of them, like `some_list 1 2 3`,
+//!   and a node with no arguments (or children) is an empty list
+//! - A node with properties or children is a table of them, like `some_nest some_int=4` or `some_nest { some_int 4 }`
+//! - A node whose children are all called `-` is a list of them, like `some_list { - 1; - 2 }`
+//! - Nodes with the same name are a list of their values, like `server { ... }` written twice
+//!
+//! Type annotations are ignored, and non-empty lists are written with `-` children.
+
+use std::collections::HashSet;
+
+use crate::{
+    document::{Document, Map},
+    Error,
+};
+
+/// A parsed node, before it's mapped onto a `Document`.
+struct Node {
+    name: String,
+    arguments: Vec<Document>,
+    properties: Map<String, Document>,
+    children: Option<Vec<Node>>,
+}
+
+/// Parses a KDL file into a `Document`.
+///
+/// # Errors
+/// This will fail if the file is malformed, or a node doesn't map onto a `Document`.
+pub fn from_str(data: &str) -> Result<Document, Error> {
+    let mut parser: Parser = Parser {
+        chars: data.chars().collect(),
+        position: 0,
+    };
+    let nodes: Vec<Node> = parser.nodes(false)?;
+    nodes_to_document(nodes)
+}
+
+/// Maps sibling nodes onto a table, making lists of nodes with the same name.
+fn nodes_to_document(nodes: Vec<Node>) -> Result<Document, Error> {
+    let mut map: Map<String, Document> = Map::new();
+    let mut repeated: HashSet<String> = HashSet::new();
+
+    for node in nodes {
+        let name: String = node.name.clone();
+        let value: Document = node_to_document(node)?;
+        match map.get_mut(&name) {
+            Some(Document::Array(values)) if repeated.contains(&name) => values.push(value),
+            Some(existing) => {
+                *existing = Document::Array(vec![existing.take(), value]);
+                repeated.insert(name);
+            }
+            None => {
+                map.insert(name, value);
+            }
+        }
+    }
+
+    Ok(Document::Object(map))
+}
+
+/// Maps a node onto its value, see the module documentation.
+fn node_to_document(node: Node) -> Result<Document, Error> {
+    let invalid = |message: &str| Error::KdlError(format!("node `{}` {message}", node.name));
+
+    if let Some(children) = node.children {
+        if !node.arguments.is_empty() {
+            return Err(invalid("can't have both arguments and children"));
+        }
+        if !children.is_empty() && children.iter().all(|child: &Node| child.name == "-") {
+            if !node.properties.is_empty() {
+                return Err(invalid("can't have both properties and `-` children"));
+            }
+            return children
+                .into_iter()
+                .map(node_to_document)
+                .collect::<Result<Vec<Document>, Error>>()
+                .map(Document::Array);
+        }
+
+        let mut document: Document = Document::Object(node.properties);
+        if let (Document::Object(map), Document::Object(children)) =
+            (&mut document, nodes_to_document(children)?)
+        {
+            map.extend(children);
+        }
+        return Ok(document);
+    }
+
+    if !node.properties.is_empty() {
+        if !node.arguments.is_empty() {
+            return Err(invalid("can't have both arguments and properties"));
+        }
+        return Ok(Document::Object(node.properties));
+    }
+
+    let mut arguments: Vec<Document> = node.arguments;
+    Ok(if arguments.len() == 1 {
+        arguments.remove(0)
+    } else {
+        Document::Array(arguments)
+    })
+}
+
+/// Whether `c` ends a line in KDL.
+const fn is_newline(c: char) -> bool {
+    matches!(
+        c,
+        '\n' | '\r' | '\x0b' | '\x0c' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+/// Whether `c` is whitespace (but not a newline) in KDL.
+fn is_space(c: char) -> bool {
+    c == '\u{feff}' || (c.is_whitespace() && !is_newline(c))
+}
+
+/// Whether `c` can be in a bare identifier.
+fn is_identifier_char(c: char) -> bool {
+    !is_space(c) && !is_newline(c) && !"\\/(){};[]=\"#".contains(c)
+}
+
+/// A KDL parser, over the characters of a file.
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).copied()
+    }
+
+    fn starts_with(&self, prefix: &str) -> bool {
+        prefix
+            .chars()
+            .enumerate()
+            .all(|(offset, c): (usize, char)| self.peek_at(offset) == Some(c))
+    }
+
+    fn error(&self, message: &str) -> Error {
+        let line: usize = self.chars[..self.position.min(self.chars.len())]
+            .iter()
+            .filter(|c: &&char| **c == '\n')
+            .count();
+        Error::KdlError(format!("line {}: {message}", line + 1))
+    }
+
+    /// Parses nodes until the end of the file, or the `}` closing a block if `block` is set.
+    fn nodes(&mut self, block: bool) -> Result<Vec<Node>, Error> {
+        let mut nodes: Vec<Node> = Vec::new();
+        loop {
+            self.skip_line_space()?;
+            match self.peek() {
+                None if block => return Err(self.error("expected `}`")),
+                None => return Ok(nodes),
+                Some('}') if block => {
+                    self.position += 1;
+                    return Ok(nodes);
+                }
+                Some('}') => return Err(self.error("unexpected `}`")),
+                _ if self.starts_with("/-") => {
+                    self.position += 2;
+                    self.skip_node_space()?;
+                    self.node()?;
+                }
+                _ => nodes.push(self.node()?),
+            }
+        }
+    }
+
+    /// Skips whitespace, newlines, comments and `;`s between nodes.
+    fn skip_line_space(&mut self) -> Result<(), Error> {
+        loop {
+            match self.peek() {
+                Some(c) if is_space(c) || is_newline(c) || c == ';' => self.position += 1,
+                Some('/') if self.peek_at(1) == Some('/') => self.skip_line_comment(),
+                Some('/') if self.peek_at(1) == Some('*') => self.skip_block_comment()?,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Skips whitespace, block comments and line continuations within a node,
+    /// returning whether anything was skipped.
+    fn skip_node_space(&mut self) -> Result<bool, Error> {
+        let start: usize = self.position;
+        loop {
+            match self.peek() {
+                Some(c) if is_space(c) => self.position += 1,
+                Some('/') if self.peek_at(1) == Some('*') => self.skip_block_comment()?,
+                Some('\\') => {
+                    self.position += 1;
+                    while self.peek().is_some_and(is_space) {
+                        self.position += 1;
+                    }
+                    if self.starts_with("//") {
+                        self.skip_line_comment();
+                    }
+                    match self.peek() {
+                        Some('\r') if self.peek_at(1) == Some('\n') => self.position += 2,
+                        Some(c) if is_newline(c) => self.position += 1,
+                        None => {}
+                        Some(_) => return Err(self.error("expected a newline after `\\`")),
+                    }
+                }
+                _ => return Ok(self.position != start),
+            }
+        }
+    }
+
+    fn skip_line_comment(&mut self) {
+        while self.peek().is_some_and(|c: char| !is_newline(c)) {
+            self.position += 1;
+        }
+    }
+
+    /// Skips a `/* */` comment, which can be nested.
+    fn skip_block_comment(&mut self) -> Result<(), Error> {
+        let mut depth: usize = 0;
+        loop {
+            if self.starts_with("/*") {
+                depth += 1;
+                self.position += 2;
+            } else if self.starts_with("*/") {
+                depth -= 1;
+                self.position += 2;
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else if self.peek().is_some() {
+                self.position += 1;
+            } else {
+                return Err(self.error("expected `*/`"));
+            }
+        }
+    }
+
+    /// Skips a `(type)` annotation, if there is one.
+    fn skip_type_annotation(&mut self) -> Result<(), Error> {
+        if self.peek() != Some('(') {
+            return Ok(());
+        }
+        self.position += 1;
+        self.skip_node_space()?;
+        self.string()?;
+        self.skip_node_space()?;
+        if self.peek() != Some(')') {
+            return Err(self.error("expected `)`"));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    /// Parses a node and its arguments, properties and children.
+    fn node(&mut self) -> Result<Node, Error> {
+        self.skip_type_annotation()?;
+        let mut node: Node = Node {
+            name: self.string()?,
+            arguments: Vec::new(),
+            properties: Map::new(),
+            children: None,
+        };
+
+        loop {
+            let spaced: bool = self.skip_node_space()?;
+            match self.peek() {
+                None | Some('}') => return Ok(node),
+                Some(';') => {
+                    self.position += 1;
+                    return Ok(node);
+                }
+                Some(c) if is_newline(c) => return Ok(node),
+                Some('/') if self.peek_at(1) == Some('/') => {
+                    self.skip_line_comment();
+                    return Ok(node);
+                }
+                _ if self.starts_with("/-") => {
+                    self.position += 2;
+                    self.skip_node_space()?;
+                    if self.peek() == Some('{') {
+                        self.position += 1;
+                        self.nodes(true)?;
+                    } else {
+                        self.entry()?;
+                    }
+                }
+                Some('{') => {
+                    if node.children.is_some() {
+                        return Err(self.error("a node can only have one children block"));
+                    }
+                    self.position += 1;
+                    node.children = Some(self.nodes(true)?);
+                }
+                _ if node.children.is_some() => {
+                    return Err(self.error("expected the node to end after its children"));
+                }
+                _ if !spaced => return Err(self.error("expected whitespace before an argument")),
+                _ => match self.entry()? {
+                    (Some(key), value) => {
+                        node.properties.insert(key, value);
+                    }
+                    (None, value) => node.arguments.push(value),
+                },
+            }
+        }
+    }
+
+    /// Parses an argument, or a property and its key.
+    fn entry(&mut self) -> Result<(Option<String>, Document), Error> {
+        let annotated: bool = self.peek() == Some('(');
+        self.skip_type_annotation()?;
+        let value: Document = self.value()?;
+
+        match value {
+            Document::String(key) if !annotated && self.peek() == Some('=') => {
+                self.position += 1;
+                self.skip_type_annotation()?;
+                Ok((Some(key), self.value()?))
+            }
+            value => Ok((None, value)),
+        }
+    }
+
+    /// Parses a value, which can be a string, number or keyword.
+    fn value(&mut self) -> Result<Document, Error> {
+        match self.peek() {
+            Some('#') if !matches!(self.peek_at(1), Some('"' | '#')) => self.keyword(),
+            Some(c) if c.is_ascii_digit() => self.number(),
+            Some('-' | '+') if self.peek_at(1).is_some_and(|c: char| c.is_ascii_digit()) => {
+                self.number()
+            }
+            _ => {
+                let bare: bool = self.peek().is_some_and(is_identifier_char)
+                    && !(self.peek() == Some('r') && matches!(self.peek_at(1), Some('"' | '#')));
+                let string: String = self.string()?;
+                // KDL 1 keywords are bare.
+                Ok(match string.as_str() {
+                    "true" if bare => Document::Bool(true),
+                    "false" if bare => Document::Bool(false),
+                    "null" if bare => Document::Null,
+                    _ => Document::String(string),
+                })
+            }
+        }
+    }
+
+    /// Parses a KDL 2 keyword, like `#true`.
+    fn keyword(&mut self) -> Result<Document, Error> {
+        self.position += 1;
+        let start: usize = self.position;
+        while self.peek().is_some_and(is_identifier_char) {
+            self.position += 1;
+        }
+        match self.chars[start..self.position]
+            .iter()
+            .collect::<String>()
+            .as_str()
+        {
+            "true" => Ok(Document::Bool(true)),
+            "false" => Ok(Document::Bool(false)),
+            "null" => Ok(Document::Null),
+            "inf" | "-inf" | "nan" => Err(self.error("infinite and NaN numbers aren't supported")),
+            _ => Err(self.error("unknown keyword")),
+        }
+    }
+
+    /// Parses a decimal, hexadecimal, octal or binary number.
+    fn number(&mut self) -> Result<Document, Error> {
+        let start: usize = self.position;
+        while self.peek().is_some_and(is_identifier_char) {
+            self.position += 1;
+        }
+        let token: String = self.chars[start..self.position]
+            .iter()
+            .filter(|c: &&char| **c != '_')
+            .collect();
+
+        let (negative, digits): (bool, &str) = match token.as_bytes().first() {
+            Some(b'-') => (true, &token[1..]),
+            Some(b'+') => (false, &token[1..]),
+            _ => (false, token.as_str()),
+        };
+        let radix: Option<(u32, &str)> = digits
+            .strip_prefix("0x")
+            .map(|digits: &str| (16, digits))
+            .or_else(|| digits.strip_prefix("0o").map(|digits: &str| (8, digits)))
+            .or_else(|| digits.strip_prefix("0b").map(|digits: &str| (2, digits)));
+
+        let number: Option<Document> = match radix {
+            Some((radix, digits)) => i64::from_str_radix(digits, radix)
+                .ok()
+                .map(|number: i64| if negative { -number } else { number }.into())
+                .or_else(|| {
+                    u64::from_str_radix(digits, radix)
+                        .ok()
+                        .filter(|_| !negative)
+                        .map(Document::from)
+                }),
+            None => token
+                .parse::<i64>()
+                .map(Document::from)
+                .or_else(|_| token.parse::<u64>().map(Document::from))
+                .ok()
+                .or_else(|| {
+                    token
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|number: &f64| number.is_finite())
+                        .map(Document::from)
+                }),
+        };
+        number.ok_or_else(|| self.error("invalid number"))
+    }
+
+    /// Parses a bare identifier, a quoted string, or a raw string.
+    fn string(&mut self) -> Result<String, Error> {
+        match self.peek() {
+            Some('"') => self.quoted_string(),
+            Some('r') if matches!(self.peek_at(1), Some('"' | '#')) => {
+                self.position += 1;
+                self.raw_string()
+            }
+            Some('#') => self.raw_string(),
+            Some(c) if is_identifier_char(c) => {
+                let start: usize = self.position;
+                while self.peek().is_some_and(is_identifier_char) {
+                    self.position += 1;
+                }
+                Ok(self.chars[start..self.position].iter().collect())
+            }
+            _ => Err(self.error("expected a string")),
+        }
+    }
+
+    /// Parses a `"` string, or a `"""` multi-line string.
+    fn quoted_string(&mut self) -> Result<String, Error> {
+        let multiline: bool = self.starts_with("\"\"\"");
+        let delimiter: &str = if multiline { "\"\"\"" } else { "\"" };
+        self.position += delimiter.len();
+
+        let mut output: String = String::new();
+        loop {
+            if self.starts_with(delimiter) {
+                self.position += delimiter.len();
+                break;
+            }
+            match self.peek() {
+                None => return Err(self.error("expected the string to end")),
+                Some('\\') => {
+                    self.position += 1;
+                    self.escape(&mut output)?;
+                }
+                Some(c) => {
+                    output.push(c);
+                    self.position += 1;
+                }
+            }
+        }
+
+        Ok(if multiline { dedent(&output) } else { output })
+    }
+
+    /// Parses the escape after a `\` in a quoted string onto `output`.
+    fn escape(&mut self, output: &mut String) -> Result<(), Error> {
+        let c: char = self
+            .peek()
+            .ok_or_else(|| self.error("expected an escape"))?;
+        self.position += 1;
+        match c {
+            'n' => output.push('\n'),
+            'r' => output.push('\r'),
+            't' => output.push('\t'),
+            'b' => output.push('\x08'),
+            'f' => output.push('\x0c'),
+            's' => output.push(' '),
+            '\\' | '"' | '/' => output.push(c),
+            'u' => {
+                let end: usize = self.chars[self.position..]
+                    .iter()
+                    .position(|c: &char| *c == '}')
+                    .ok_or_else(|| self.error("expected `}`"))?;
+                // `end` is at least 2 for the shortest escape, `\u{0}`.
+                if self.peek() != Some('{') || end < 2 {
+                    return Err(self.error("invalid unicode escape"));
+                }
+                let hex: String = self.chars[self.position + 1..self.position + end]
+                    .iter()
+                    .collect();
+                let c: char = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| self.error("invalid unicode escape"))?;
+                output.push(c);
+                self.position += end + 1;
+            }
+            // Escaped whitespace is skipped.
+            c if is_space(c) || is_newline(c) => {
+                while self
+                    .peek()
+                    .is_some_and(|c: char| is_space(c) || is_newline(c))
+                {
+                    self.position += 1;
+                }
+            }
+            _ => return Err(self.error("invalid escape")),
+        }
+        Ok(())
+    }
+
+    /// Parses a raw string like `#"..."#` (or `r#"..."#` in KDL 1), after the `r`.
+    fn raw_string(&mut self) -> Result<String, Error> {
+        let mut hashes: usize = 0;
+        while self.peek() == Some('#') {
+            hashes += 1;
+            self.position += 1;
+        }
+        let multiline: bool = self.starts_with("\"\"\"");
+        let quote: &str = if multiline { "\"\"\"" } else { "\"" };
+        if !self.starts_with(quote) {
+            return Err(self.error("expected `\"`"));
+        }
+        self.position += quote.len();
+
+        let end: String = format!("{quote}{}", "#".repeat(hashes));
+        let start: usize = self.position;
+        while !self.starts_with(&end) {
+            if self.peek().is_none() {
+                return Err(self.error("expected the raw string to end"));
+            }
+            self.position += 1;
+        }
+        let output: String = self.chars[start..self.position].iter().collect();
+        self.position += end.chars().count();
+
+        Ok(if multiline { dedent(&output) } else { output })
+    }
+}
+
+/// Removes the first newline of a multi-line string,
+/// and the whitespace before its closing quotes from the start of every line.
+fn dedent(data: &str) -> String {
+    let data: &str = data
+        .strip_prefix("\r\n")
+        .or_else(|| data.strip_prefix('\n'))
+        .unwrap_or(data);
+    let (body, indent): (&str, &str) = data.rsplit_once('\n').unwrap_or(("", data));
+    if !indent.chars().all(is_space) {
+        return data.to_string();
+    }
+
+    body.lines()
+        .map(|line: &str| line.strip_prefix(indent).unwrap_or(line.trim_start()))
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+/// Converts a `Document` into a KDL 2 file, see the module documentation.
+///
+/// # Errors
+/// This will fail if `document` isn't a table.
+pub fn to_string(document: &Document) -> Result<String, Error> {
+    let Document::Object(map) = document else {
+        return Err(Error::KdlError(
+            "only tables can be written as KDL nodes".to_string(),
+        ));
+    };
+
+    let mut output: String = String::new();
+    for (key, value) in map {
+        write_node(key, value, 0, &mut output);
+    }
+    Ok(output)
+}
+
+/// Writes a node called `name` with the value `value` to `output`, indented `depth` times.
+fn write_node(name: &str, value: &Document, depth: usize, output: &mut String) {
+    output.push_str(&"    ".repeat(depth));
+    output.push_str(&identifier(name));
+
+    match value {
+        Document::Null => output.push_str(" #null"),
+        Document::Bool(boolean) => output.push_str(if *boolean { " #true" } else { " #false" }),
+        Document::Number(number) => {
+            output.push(' ');
+            output.push_str(&number.to_string());
+        }
+        Document::String(string) => {
+            output.push(' ');
+            output.push_str(&quote(string));
+        }
+        Document::Array(values) if values.is_empty() => {}
+        Document::Object(map) if map.is_empty() => output.push_str(" {}"),
+        Document::Array(values) => {
+            output.push_str(" {\n");
+            for value in values {
+                write_node("-", value, depth + 1, output);
+            }
+            output.push_str(&"    ".repeat(depth));
+            output.push('}');
+        }
+        Document::Object(map) => {
+            output.push_str(" {\n");
+            for (key, value) in map {
+                write_node(key, value, depth + 1, output);
+            }
+            output.push_str(&"    ".repeat(depth));
+            output.push('}');
+        }
+    }
+    output.push('\n');
+}
+
+/// Writes `name` as a bare identifier if it can be one, or quotes it.
+fn identifier(name: &str) -> String {
+    let looks_like_number: bool = name
+        .trim_start_matches(['-', '+'])
+        .starts_with(|c: char| c.is_ascii_digit())
+        || name.starts_with('.');
+    let keyword: bool = matches!(name, "true" | "false" | "null" | "inf" | "-inf" | "nan");
+
+    if name.is_empty() || looks_like_number || keyword || !name.chars().all(is_identifier_char) {
+        quote(name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Quotes and escapes a string.
+fn quote(data: &str) -> String {
+    let mut output: String = String::with_capacity(data.len() + 2);
+    output.push('"');
+    for c in data.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            '\x08' => output.push_str("\\b"),
+            '\x0c' => output.push_str("\\f"),
+            c if c.is_control() => {
+                let escaped: String = format!("\\u{{{:x}}}", u32::from(c));
+                output.push_str(&escaped);
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
@@ -1,14 +1,39 @@
 //! *A customizable and unopinionated configuration library.*
 
 pub mod config;
+pub mod diff;
+pub mod document;
+#[cfg(feature = "dotenv")]
+pub mod dotenv;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+pub mod format;
+#[cfg(feature = "kdl")]
+pub mod kdl;
+mod migration;
+pub mod patch;
+#[cfg(feature = "properties")]
+pub mod properties;
+pub mod secret;
+pub mod shared;
+pub mod source;
 mod sparse;
+pub mod warning;
+#[cfg(feature = "xml")]
+pub mod xml;
 
 use std::io;
 
 #[allow(clippy::wildcard_imports)]
 pub use cogwheel_macro::*;
 pub use config::Configuration;
+pub use diff::diff;
+pub use format::Format;
+pub use secret::Secret;
+pub use shared::SharedFile;
+pub use source::Source;
 pub use sparse::Sparse;
+pub use warning::Warning;
 
 #[derive(Debug, thiserror::Error)]
 /// The representation of an error from Cogwheel.
@@ -23,14 +48,40 @@ pub enum Error {
     CouldNotGuess,
     #[error("no configuration location specified, use something like `use_*`, or `make_*`")]
     NoConfigurationSpecified,
+    #[error("configuration is not valid UTF-8, or UTF-16 with a byte order mark")]
+    InvalidEncoding,
+    #[error("environment variable `{0}` is not set or is not valid unicode")]
+    MissingEnvironmentVariable(String),
+    #[error("profile `{0}` does not exist in the configuration")]
+    UnknownProfile(String),
+    #[error("`{0}` does not exist in the configuration")]
+    MissingPath(String),
+    #[error("more than one file is named `{0}`")]
+    DuplicateInstance(String),
+    #[error("the `version` key must be a positive integer")]
+    InvalidVersion,
+    #[error("configuration is version {0}, which is newer than any registered migration")]
+    UnsupportedVersion(u64),
+    #[error("no migration registered from version {0}")]
+    MissingMigration(u64),
+    #[error("could not apply patch, {0}")]
+    InvalidPatch(String),
+    #[error("could not load configuration from {0}")]
+    SourceError(String, #[source] Box<Error>),
 
     #[error("error while reading or writing file")]
     FileError(#[from] io::Error),
 
-    #[cfg(feature = "json")]
+    #[error("error while de/serializing {0}")]
+    FormatError(String, #[source] Box<dyn std::error::Error + Send + Sync>),
+
     #[error("error while de/serializing JSON")]
     JsonError(#[from] serde_json::Error),
 
+    #[cfg(feature = "json5")]
+    #[error("error while deserializing JSON5")]
+    Json5Error(#[from] json5::Error),
+
     #[cfg(feature = "toml")]
     #[error("error while deserializing TOML")]
     TomlDeError(#[from] toml::de::Error),
@@ -41,4 +92,69 @@ pub enum Error {
     #[cfg(feature = "yaml")]
     #[error("error while de/serializing YAML")]
     YamlError(#[from] serde_yml::Error),
+
+    #[cfg(feature = "ron")]
+    #[error("error while deserializing RON")]
+    RonDeError(#[from] ron::error::SpannedError),
+    #[cfg(feature = "ron")]
+    #[error("error while serializing RON")]
+    RonSerError(#[from] ron::Error),
+
+    #[cfg(feature = "hcl")]
+    #[error("error while de/serializing HCL")]
+    HclError(#[from] hcl::Error),
+
+    #[cfg(feature = "dotenv")]
+    #[error("invalid .env file on line {0}, expected `KEY=value`")]
+    InvalidDotenv(usize),
+    #[cfg(feature = "dotenv")]
+    #[error("`{0}` can't be written to a .env file, only tables, strings, numbers and booleans can")]
+    UnsupportedDotenvValue(String),
+
+    #[cfg(feature = "properties")]
+    #[error("invalid `\\uXXXX` escape or conflicting key in .properties file on line {0}")]
+    InvalidProperties(usize),
+    #[cfg(feature = "properties")]
+    #[error("`{0}` can't be written to a .properties file, only tables, strings, numbers and booleans can")]
+    UnsupportedPropertiesValue(String),
+
+    #[cfg(feature = "kdl")]
+    #[error("invalid KDL, {0}")]
+    KdlError(String),
+
+    #[cfg(feature = "xml")]
+    #[error("invalid XML, {0}")]
+    XmlError(String),
+
+    #[cfg(feature = "msgpack")]
+    #[error("error while deserializing MessagePack")]
+    MsgpackDeError(#[from] rmp_serde::decode::Error),
+    #[cfg(feature = "msgpack")]
+    #[error("error while serializing MessagePack")]
+    MsgpackSerError(#[from] rmp_serde::encode::Error),
+
+    #[cfg(feature = "cbor")]
+    #[error("error while deserializing CBOR")]
+    CborDeError(#[from] ciborium::de::Error<io::Error>),
+    #[cfg(feature = "cbor")]
+    #[error("error while serializing CBOR")]
+    CborSerError(#[from] ciborium::ser::Error<io::Error>),
+
+    #[cfg(any(feature = "msgpack", feature = "cbor"))]
+    #[error("{0} is a binary format, so it can only be written as bytes")]
+    BinaryFormat(String),
+
+    #[cfg(feature = "encryption")]
+    #[error("encryption key must be the base64 of exactly 32 bytes")]
+    InvalidKey,
+    #[cfg(feature = "encryption")]
+    #[error("could not encrypt value")]
+    EncryptionError,
+    #[cfg(feature = "encryption")]
+    #[error("could not decrypt value, was it encrypted with a different key?")]
+    DecryptionError,
+
+    #[cfg(feature = "url")]
+    #[error("error while fetching URL")]
+    UrlError(#[from] ureq::Error),
 }
@@ -0,0 +1,83 @@
+use crate::{document::Document, Error};
+
+/// The key that a file's version is stored under.
+pub(crate) const VERSION_KEY: &str = "version";
+
+/// The version of files that don't have a `version` key.
+pub(crate) const UNVERSIONED: u64 = 1;
+
+/// A function that upgrades a `Document` from one version to the next.
+type Migration = Box<dyn Fn(&mut Document) + Send + Sync>;
+
+/// The migrations registered on a `ConfigurationBuilder`, see `ConfigurationBuilder::migration`.
+#[derive(Default)]
+pub(crate) struct Migrations(Vec<(u64, u64, Migration)>);
+
+impl Migrations {
+    pub(crate) fn push(&mut self, from: u64, to: u64, migration: Migration) {
+        self.0.push((from, to, migration));
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The newest version any migration upgrades to.
+    pub(crate) fn current(&self) -> u64 {
+        self.0
+            .iter()
+            .map(|(_, to, _)| *to)
+            .max()
+            .unwrap_or(UNVERSIONED)
+    }
+
+    /// Upgrades `document` to the `current` version, one migration at a time,
+    /// returning whether anything had to be migrated.
+    ///
+    /// # Errors
+    /// This will fail if:
+    /// - The `version` key isn't a positive integer
+    /// - The file is newer than the `current` version
+    /// - There's no migration from one of the versions on the way
+    pub(crate) fn apply(&self, document: &mut Document) -> Result<bool, Error> {
+        let current: u64 = self.current();
+        let mut version: u64 = match document.get(VERSION_KEY) {
+            // Formats like `.env` files only have strings.
+            Some(version) => version
+                .as_u64()
+                .or_else(|| version.as_str()?.parse().ok())
+                .ok_or(Error::InvalidVersion)?,
+            None => UNVERSIONED,
+        };
+
+        if version > current {
+            return Err(Error::UnsupportedVersion(version));
+        }
+        if version == current {
+            return Ok(false);
+        }
+
+        while version < current {
+            let (_, to, migration): &(u64, u64, Migration) = self
+                .0
+                .iter()
+                .find(|(from, to, _)| *from == version && *to > version)
+                .ok_or(Error::MissingMigration(version))?;
+            migration(document);
+            version = *to;
+        }
+
+        self.stamp(document);
+        Ok(true)
+    }
+
+    /// Sets the `version` key of `document` to the `current` version, keeping it at the top.
+    pub(crate) fn stamp(&self, document: &mut Document) {
+        if let Document::Object(map) = document {
+            map.shift_remove(VERSION_KEY);
+            let rest: serde_json::Map<String, Document> = std::mem::take(map);
+            map.insert(VERSION_KEY.to_string(), self.current().into());
+            map.extend(rest);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! JSON Merge Patch ([RFC 7396](https://www.rfc-editor.org/rfc/rfc7396))
+//! and JSON Patch ([RFC 6902](https://www.rfc-editor.org/rfc/rfc6902)) for `Document`s.
+//!
+//! These are usually used through `ConfigurationBuilder::apply_merge_patch` and `ConfigurationBuilder::apply_patch`,
+//! which check that the result is still valid before keeping it.
+
+use crate::{
+    document::{Document, Map},
+    Error,
+};
+
+/// Applies a JSON Merge Patch to `document`.
+/// Tables in `patch` are merged key by key, `null` removes a key, and anything else replaces what's there.
+///
+/// ```
+/// let mut document: Document = serde_json::json!({ "a": 1, "nest": { "b": 2, "c": 3 } });
+/// merge_patch(&mut document, &serde_json::json!({ "a": null, "nest": { "c": 4 } }));
+///
+/// assert_eq!(document, serde_json::json!({ "nest": { "b": 2, "c": 4 } }));
+/// ```
+pub fn merge_patch(document: &mut Document, patch: &Document) {
+    let Document::Object(patch) = patch else {
+        *document = patch.clone();
+        return;
+    };
+    if !document.is_object() {
+        *document = Document::Object(Map::new());
+    }
+
+    if let Document::Object(map) = document {
+        for (key, value) in patch {
+            if value.is_null() {
+                map.shift_remove(key);
+            } else {
+                merge_patch(map.entry(key).or_insert(Document::Null), value);
+            }
+        }
+    }
+}
+
+/// Applies a JSON Patch (an array of operations like `{ "op": "replace", "path": "/a", "value": 1 }`)
+/// to `document`. If any operation fails, `document` is left as it was.
+///
+/// ```
+/// let mut document: Document = serde_json::json!({ "nest": { "b": 2 } });
+/// patch(&mut document, &serde_json::json!([
+///     { "op": "test", "path": "/nest/b", "value": 2 },
+///     { "op": "move", "from": "/nest/b", "path": "/b" },
+/// ]))?;
+///
+/// assert_eq!(document, serde_json::json!({ "nest": {}, "b": 2 }));
+/// ```
+///
+/// # Errors
+/// This will fail if:
+/// - `patch` isn't an array of valid operations
+/// - An operation's path doesn't exist, or a `test` operation fails
+pub fn patch(document: &mut Document, patch: &Document) -> Result<(), Error> {
+    let operations: &Vec<Document> = patch
+        .as_array()
+        .ok_or_else(|| invalid("a patch must be an array of operations"))?;
+    let mut patched: Document = document.clone();
+
+    for (index, operation) in operations.iter().enumerate() {
+        apply_operation(&mut patched, operation)
+            .map_err(|message: String| invalid(&format!("operation {index}: {message}")))?;
+    }
+
+    *document = patched;
+    Ok(())
+}
+
+fn invalid(message: &str) -> Error {
+    Error::InvalidPatch(message.to_string())
+}
+
+/// Applies one JSON Patch operation to `document`.
+fn apply_operation(document: &mut Document, operation: &Document) -> Result<(), String> {
+    let field = |name: &str| -> Result<&Document, String> {
+        operation
+            .get(name)
+            .ok_or_else(|| format!("missing `{name}`"))
+    };
+    let pointer = |name: &str| -> Result<Vec<String>, String> {
+        parse_pointer(
+            field(name)?
+                .as_str()
+                .ok_or_else(|| format!("`{name}` must be a string"))?,
+        )
+    };
+
+    let path: Vec<String> = pointer("path")?;
+    match field("op")?.as_str() {
+        Some("add") => add(document, &path, field("value")?.clone()),
+        Some("remove") => remove(document, &path).map(drop),
+        Some("replace") => {
+            let target: &mut Document = get_mut(document, &path)?;
+            *target = field("value")?.clone();
+            Ok(())
+        }
+        Some("move") => {
+            let from: Vec<String> = pointer("from")?;
+            if path.len() > from.len() && path.starts_with(&from) {
+                return Err("cannot move a value into itself".to_string());
+            }
+            let value: Document = remove(document, &from)?;
+            add(document, &path, value)
+        }
+        Some("copy") => {
+            let value: Document = get_mut(document, &pointer("from")?)?.clone();
+            add(document, &path, value)
+        }
+        Some("test") => {
+            if get_mut(document, &path)? == field("value")? {
+                Ok(())
+            } else {
+                Err(format!("test failed at `/{}`", path.join("/")))
+            }
+        }
+        _ => Err("`op` must be one of add, remove, replace, move, copy or test".to_string()),
+    }
+}
+
+/// Splits a JSON Pointer like `/some_nest/some_int` into its unescaped keys.
+fn parse_pointer(pointer: &str) -> Result<Vec<String>, String> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    pointer
+        .strip_prefix('/')
+        .ok_or_else(|| format!("`{pointer}` is not a JSON pointer"))
+        .map(|pointer: &str| {
+            pointer
+                .split('/')
+                .map(|key: &str| key.replace("~1", "/").replace("~0", "~"))
+                .collect()
+        })
+}
+
+/// Gets the value at `path`, which has to exist.
+fn get_mut<'a>(document: &'a mut Document, path: &[String]) -> Result<&'a mut Document, String> {
+    path.iter()
+        .try_fold(document, |document: &mut Document, key: &String| {
+            match document {
+                Document::Object(map) => map.get_mut(key),
+                Document::Array(array) => key
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index: usize| array.get_mut(index)),
+                _ => None,
+            }
+            .ok_or_else(|| format!("`/{}` does not exist", path.join("/")))
+        })
+}
+
+/// Adds `value` at `path`, inserting into arrays and replacing keys in tables.
+fn add(document: &mut Document, path: &[String], value: Document) -> Result<(), String> {
+    let Some((key, parent)) = path.split_last() else {
+        *document = value;
+        return Ok(());
+    };
+
+    match get_mut(document, parent)? {
+        Document::Object(map) => {
+            map.insert(key.clone(), value);
+            Ok(())
+        }
+        Document::Array(array) if key == "-" => {
+            array.push(value);
+            Ok(())
+        }
+        Document::Array(array) => match key.parse::<usize>() {
+            Ok(index) if index <= array.len() => {
+                array.insert(index, value);
+                Ok(())
+            }
+            _ => Err(format!("`{key}` is not a valid array index")),
+        },
+        _ => Err(format!("`/{}` is not a table or array", parent.join("/"))),
+    }
+}
+
+/// Removes the value at `path`, which has to exist.
+fn remove(document: &mut Document, path: &[String]) -> Result<Document, String> {
+    let Some((key, parent)) = path.split_last() else {
+        return Ok(std::mem::take(document));
+    };
+
+    match get_mut(document, parent)? {
+        Document::Object(map) => map.shift_remove(key),
+        Document::Array(array) => key
+            .parse::<usize>()
+            .ok()
+            .filter(|index: &usize| *index < array.len())
+            .map(|index: usize| array.remove(index)),
+        _ => None,
+    }
+    .ok_or_else(|| format!("`/{}` does not exist", path.join("/")))
+}
@@ -0,0 +1,217 @@
+//! Reading and writing Java `.properties` files.
+//!
+//! Each line is a `key=value`, `key: value` or `key value`, and lines starting with `#` or `!` are comments.
+//! A line ending in `\` continues on the next one, without the next one's leading whitespace.
+//! Keys and values can have `\t`, `\n`, `\r`, `\f` and `\uXXXX` escapes, and `\` before anything else is dropped.
+//!
+//! Dotted keys are nested, so `some_nest.some_int=4` sets `some_nest.some_int`,
+//! and a key can't be both a value and a table (like `a=1` and `a.b=2`).
+//! Every value is a string, which is read as a number or boolean where `T` has one.
+
+use crate::{
+    document::{Document, Map},
+    Error,
+};
+
+/// Parses a `.properties` file into a `Document`.
+///
+/// # Errors
+/// This will fail if there's a malformed `\uXXXX` escape, or a key is both a value and a table.
+pub fn from_str(data: &str) -> Result<Document, Error> {
+    let mut document: Document = Document::Object(Map::new());
+    let mut logical: Option<(usize, String)> = None;
+
+    for (index, line) in data.lines().enumerate() {
+        let line: &str = line.trim_start_matches(is_whitespace);
+        if logical.is_none() && (line.is_empty() || line.starts_with(['#', '!'])) {
+            continue;
+        }
+
+        // An odd number of trailing `\`s means the last one escapes the line break.
+        let continues: bool = (line.len() - line.trim_end_matches('\\').len()) % 2 == 1;
+        let line: &str = if continues {
+            &line[..line.len() - 1]
+        } else {
+            line
+        };
+
+        let (_, logical_line): &mut (usize, String) =
+            logical.get_or_insert_with(|| (index + 1, String::new()));
+        logical_line.push_str(line);
+
+        if !continues {
+            if let Some((number, line)) = logical.take() {
+                let (key, value): (String, String) = parse_line(&line, number)?;
+                insert(&mut document, &key, value, number)?;
+            }
+        }
+    }
+    if let Some((number, line)) = logical {
+        let (key, value): (String, String) = parse_line(&line, number)?;
+        insert(&mut document, &key, value, number)?;
+    }
+
+    Ok(document)
+}
+
+/// Sets the value at a dotted `key` from line `number`,
+/// failing instead of replacing a table with a value or a value with a table.
+fn insert(document: &mut Document, key: &str, value: String, number: usize) -> Result<(), Error> {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last: &str = parts.pop().unwrap_or_default();
+
+    let mut table: &mut Map<String, Document> = document
+        .as_object_mut()
+        .ok_or(Error::InvalidProperties(number))?;
+    for part in parts {
+        table = table
+            .entry(part)
+            .or_insert_with(|| Document::Object(Map::new()))
+            .as_object_mut()
+            .ok_or(Error::InvalidProperties(number))?;
+    }
+    if table.get(last).is_some_and(Document::is_object) {
+        return Err(Error::InvalidProperties(number));
+    }
+
+    table.insert(last.to_string(), value.into());
+    Ok(())
+}
+
+/// Whether `c` is whitespace in a `.properties` file.
+const fn is_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\x0c')
+}
+
+/// Splits a logical line (with continuations joined) into its unescaped key and value.
+fn parse_line(line: &str, number: usize) -> Result<(String, String), Error> {
+    let mut escaped: bool = false;
+    let end: usize = line
+        .char_indices()
+        .find(|(_, c): &(usize, char)| {
+            if escaped {
+                escaped = false;
+                return false;
+            }
+            escaped = *c == '\\';
+            *c == '=' || *c == ':' || is_whitespace(*c)
+        })
+        .map_or(line.len(), |(index, _): (usize, char)| index);
+
+    let value: &str = line[end..].trim_start_matches(is_whitespace);
+    let value: &str = value
+        .strip_prefix(['=', ':'])
+        .map_or(value, |value: &str| value.trim_start_matches(is_whitespace));
+
+    Ok((unescape(&line[..end], number)?, unescape(value, number)?))
+}
+
+/// Replaces the escapes in a key or value from line `number`.
+fn unescape(data: &str, number: usize) -> Result<String, Error> {
+    let mut output: String = String::with_capacity(data.len());
+    let mut units: Vec<u16> = Vec::new();
+    let mut chars = data.chars();
+
+    while let Some(c) = chars.next() {
+        let c: char = match c {
+            '\\' => match chars.next() {
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    let unit: u16 = u16::from_str_radix(&hex, 16)
+                        .ok()
+                        .filter(|_| hex.len() == 4)
+                        .ok_or(Error::InvalidProperties(number))?;
+                    // Characters outside of the BMP are written as two escaped surrogates.
+                    units.push(unit);
+                    continue;
+                }
+                Some('t') => '\t',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('f') => '\x0c',
+                Some(c) => c,
+                None => break,
+            },
+            c => c,
+        };
+        flush_units(&mut units, &mut output, number)?;
+        output.push(c);
+    }
+    flush_units(&mut units, &mut output, number)?;
+
+    Ok(output)
+}
+
+/// Pushes the UTF-16 from `\uXXXX` escapes onto `output`.
+fn flush_units(units: &mut Vec<u16>, output: &mut String, number: usize) -> Result<(), Error> {
+    for c in char::decode_utf16(units.drain(..)) {
+        output.push(c.map_err(|_| Error::InvalidProperties(number))?);
+    }
+    Ok(())
+}
+
+/// Converts a `Document` into a `.properties` file, with nested keys joined by `.`.
+/// Anything that isn't ASCII is written as a `\uXXXX` escape, and `null`s are left out.
+///
+/// # Errors
+/// This will fail if `document` isn't a table, or has an array in it.
+pub fn to_string(document: &Document) -> Result<String, Error> {
+    let mut output: String = String::new();
+    write_table("", document, &mut output)?;
+    Ok(output)
+}
+
+/// Writes every value in `document` to `output`, with their keys starting with `prefix`.
+fn write_table(prefix: &str, document: &Document, output: &mut String) -> Result<(), Error> {
+    let Document::Object(map) = document else {
+        return Err(Error::UnsupportedPropertiesValue(prefix.to_string()));
+    };
+
+    for (key, value) in map {
+        let key: String = format!("{prefix}{key}");
+        let value: String = match value {
+            Document::Null => continue,
+            Document::Bool(boolean) => boolean.to_string(),
+            Document::Number(number) => number.to_string(),
+            Document::String(string) => escape(string, false),
+            Document::Object(_) => {
+                write_table(&format!("{key}."), value, output)?;
+                continue;
+            }
+            Document::Array(_) => return Err(Error::UnsupportedPropertiesValue(key)),
+        };
+
+        output.push_str(&escape(&key, true));
+        output.push('=');
+        output.push_str(&value);
+        output.push('\n');
+    }
+    Ok(())
+}
+
+/// Escapes a key (if `key` is set) or a value.
+fn escape(data: &str, key: bool) -> String {
+    let mut output: String = String::with_capacity(data.len());
+    for (index, c) in data.chars().enumerate() {
+        match c {
+            '\t' => output.push_str("\\t"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\x0c' => output.push_str("\\f"),
+            '\\' | '=' | ':' | '#' | '!' => {
+                output.push('\\');
+                output.push(c);
+            }
+            ' ' if key || index == 0 => output.push_str("\\ "),
+            c if c.is_ascii() && !c.is_ascii_control() => output.push(c),
+            c => {
+                let mut units: [u16; 2] = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    let escaped: String = format!("\\u{unit:04x}");
+                    output.push_str(&escaped);
+                }
+            }
+        }
+    }
+    output
+}
@@ -0,0 +1,122 @@
+use std::{
+    cell::Cell,
+    fmt::{self, Debug, Display},
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroize;
+
+/// The placeholder written in place of a `Secret` when it isn't exposed.
+pub const REDACTED: &str = "[REDACTED]";
+
+thread_local! {
+    static EXPOSED: Cell<bool> = const { Cell::new(false) };
+    static REDACTED_ANY: Cell<bool> = const { Cell::new(false) };
+}
+
+/// A wrapper for sensitive configuration values, like passwords or API keys.
+///
+/// A `Secret` deserializes exactly like the `T` inside of it,
+/// but prints `[REDACTED]` with `Debug` and `Display`,
+/// serializes as `[REDACTED]` unless exposed (see `ConfigurationBuilder::expose_secrets` or `exposed`),
+/// and zeroes its memory when dropped.
+///
+/// ```
+/// #[derive(Debug, Configuration, Serialize, Deserialize)]
+/// struct DatabaseConfig {
+///     user: String,
+///     password: Secret<String>,
+/// }
+///
+/// // Prints `DatabaseConfig { user: "admin", password: [REDACTED] }`
+/// println!("{config:?}");
+/// // Use `expose` when you actually need the value.
+/// connect(&config.user, config.password.expose());
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret<T: Zeroize>(T);
+
+impl<T: Zeroize> Secret<T> {
+    /// Wraps `value` in a `Secret`.
+    pub const fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Gets a reference to the value inside of this `Secret`.
+    pub const fn expose(&self) -> &T {
+        &self.0
+    }
+
+    /// Gets a mutable reference to the value inside of this `Secret`.
+    pub fn expose_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: Zeroize> Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T: Zeroize> Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<'de, T: Zeroize + Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self)
+    }
+}
+
+impl<T: Zeroize + Serialize> Serialize for Secret<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if EXPOSED.get() {
+            self.0.serialize(serializer)
+        } else {
+            REDACTED_ANY.set(true);
+            serializer.serialize_str(REDACTED)
+        }
+    }
+}
+
+/// Runs `f` with every `Secret` serializing its real value instead of `[REDACTED]`.
+///
+/// ```
+/// let file: String = cogwheel::secret::exposed(|| toml::to_string(&config))?;
+/// ```
+pub fn exposed<R>(f: impl FnOnce() -> R) -> R {
+    /// Restores the previous state, even if `f` panics.
+    struct Guard(bool);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            EXPOSED.set(self.0);
+        }
+    }
+
+    let _guard: Guard = Guard(EXPOSED.replace(true));
+    f()
+}
+
+/// Runs `f`, also returning whether any `Secret` was serialized as `[REDACTED]` while it ran.
+pub(crate) fn redacted<R>(f: impl FnOnce() -> R) -> (R, bool) {
+    let before: bool = REDACTED_ANY.replace(false);
+    let result: R = f();
+    let redacted: bool = REDACTED_ANY.replace(before || REDACTED_ANY.get());
+    (result, redacted)
+}
@@ -0,0 +1,76 @@
+//! Files that several `Configuration`s are read from.
+//!
+//! A `SharedFile` is parsed once, then each subsystem reads its own table of it
+//! with `ConfigurationBuilder::use_section`, with its own defaults, options and errors.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{config::Configuration, document::Document, format::Format, Error};
+
+/// A parsed file that `Configuration`s can be read from section by section.
+///
+/// ```
+/// let file: SharedFile = SharedFile::open("./config.toml", ConfigurationVariant::Toml)?;
+///
+/// let server: ServerConfig = file.section("server")?;
+/// let database: DatabaseConfig = file.section("database")?;
+/// ```
+pub struct SharedFile {
+    pub(crate) document: Document,
+    pub(crate) file: Option<(PathBuf, Arc<dyn Format>)>,
+    pub(crate) only_strings: bool,
+}
+
+impl SharedFile {
+    /// Attempts to read and parse the file at `path`, which is in `format`.
+    ///
+    /// # Errors
+    /// This will fail if the file can't be read or is malformed.
+    pub fn open<P: AsRef<Path> + ?Sized>(
+        path: &P,
+        format: impl Format + 'static,
+    ) -> Result<Self, Error> {
+        let path: &Path = path.as_ref();
+        let data: Vec<u8> = fs::read(path)?;
+        #[cfg(feature = "compression")]
+        let data: Vec<u8> = crate::config::decompress(path, data)?;
+
+        Ok(Self {
+            document: format.parse_bytes(&data)?,
+            only_strings: format.only_strings(),
+            file: Some((path.to_path_buf(), Arc::new(format))),
+        })
+    }
+
+    /// Attempts to parse a string in `format`.
+    /// Sections read from it can't be saved, since there's no file to save them to.
+    ///
+    /// # Errors
+    /// This will fail if the string is malformed.
+    pub fn parse(data: &str, format: impl Format) -> Result<Self, Error> {
+        Ok(Self {
+            document: format.parse(data)?,
+            file: None,
+            only_strings: format.only_strings(),
+        })
+    }
+
+    /// The whole parsed file.
+    #[must_use]
+    pub fn document(&self) -> &Document {
+        &self.document
+    }
+
+    /// Attempts to read the table at a dotted `key` into `T`, with no other options.
+    /// Use `ConfigurationBuilder::use_section` to set any, like `lenient`.
+    ///
+    /// # Errors
+    /// This will fail if there's nothing at `key`, or the table isn't valid for `T`.
+    pub fn section<T: Configuration>(&self, key: &str) -> Result<T, Error> {
+        T::builder().use_section(self, key)?.build()
+    }
+}
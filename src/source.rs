@@ -0,0 +1,181 @@
+//! Places a configuration can come from.
+//!
+//! Sources are layered with `ConfigurationBuilder::add_source`, each one over the ones before it.
+//! Files, strings and environment variables are built in, and anything else
+//! (like a database table) can be added by implementing `Source`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    document::{self, Document, Map},
+    format::Format,
+    Error,
+};
+
+/// A place a configuration can come from.
+///
+/// ```
+/// /// Settings stored in a `settings (key, value)` table.
+/// struct SettingsTable(rusqlite::Connection);
+///
+/// impl Source for SettingsTable {
+///     fn label(&self) -> String {
+///         "the settings table".to_string()
+///     }
+///
+///     fn load(&self) -> Result<Document, Error> {
+///         let mut document: Document = Document::Object(Map::new());
+///         for (key, value) in self.rows()? {
+///             document::insert_path(&mut document, &key, value.into());
+///         }
+///         Ok(document)
+///     }
+/// }
+///
+/// let config: SomeBasicConfig = SomeBasicConfig::builder()
+///     .add_source(FileSource::new("./config.toml", ConfigurationVariant::Toml))?
+///     .add_source(SettingsTable(connection))?
+///     .build()?;
+/// ```
+pub trait Source {
+    /// Where this source is, for errors and `ConfigurationBuilder::source_of`, like `./config.toml`.
+    fn label(&self) -> String;
+
+    /// Reads this source into a `Document`.
+    ///
+    /// # Errors
+    /// This should fail if the source can't be read or is malformed.
+    fn load(&self) -> Result<Document, Error>;
+
+    /// Whether every value this source loads is a string, like environment variables,
+    /// which makes them read as numbers or booleans where `T` has them. This is `false` by default.
+    fn only_strings(&self) -> bool {
+        false
+    }
+}
+
+/// A file of a given format.
+pub struct FileSource {
+    path: PathBuf,
+    format: Arc<dyn Format>,
+}
+
+impl FileSource {
+    /// Creates a source for the file at `path`, which is in `format`.
+    #[must_use]
+    pub fn new<P: AsRef<Path> + ?Sized>(path: &P, format: impl Format + 'static) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            format: Arc::new(format),
+        }
+    }
+}
+
+impl Source for FileSource {
+    fn label(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn load(&self) -> Result<Document, Error> {
+        let data: Vec<u8> = fs::read(&self.path)?;
+        #[cfg(feature = "compression")]
+        let data: Vec<u8> = crate::config::decompress(&self.path, data)?;
+        self.format.parse_bytes(&data)
+    }
+
+    fn only_strings(&self) -> bool {
+        self.format.only_strings()
+    }
+}
+
+/// A string of a given format.
+pub struct StrSource {
+    data: String,
+    format: Arc<dyn Format>,
+}
+
+impl StrSource {
+    /// Creates a source for `data`, which is in `format`.
+    #[must_use]
+    pub fn new(data: impl Into<String>, format: impl Format + 'static) -> Self {
+        Self {
+            data: data.into(),
+            format: Arc::new(format),
+        }
+    }
+}
+
+impl Source for StrSource {
+    fn label(&self) -> String {
+        format!("a {} string", self.format.name())
+    }
+
+    fn load(&self) -> Result<Document, Error> {
+        self.format.parse(&self.data)
+    }
+
+    fn only_strings(&self) -> bool {
+        self.format.only_strings()
+    }
+}
+
+/// Environment variables starting with a prefix.
+///
+/// The prefix and the `_` after it are removed, the rest is lowercased,
+/// and `__` separates nested keys, so `APP_SOME_NEST__SOME_INT=4` sets `some_nest.some_int`.
+/// Values are kept as strings, which are read as numbers or booleans where `T` has them.
+pub struct EnvSource {
+    prefix: String,
+}
+
+impl EnvSource {
+    /// Creates a source for the environment variables starting with `prefix` and a `_`.
+    #[must_use]
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: format!("{prefix}_"),
+        }
+    }
+}
+
+impl Source for EnvSource {
+    fn label(&self) -> String {
+        format!("environment variables starting with `{}`", self.prefix)
+    }
+
+    fn load(&self) -> Result<Document, Error> {
+        let mut document: Document = Document::Object(Map::new());
+        for (key, value) in env::vars() {
+            if let Some(key) = key.strip_prefix(&self.prefix) {
+                let path: String = key.to_lowercase().replace("__", ".");
+                document::insert_path(&mut document, &path, value.into());
+            }
+        }
+        Ok(document)
+    }
+
+    fn only_strings(&self) -> bool {
+        true
+    }
+}
+
+/// Pushes the dotted path of every value in `document` that isn't a table onto `paths`.
+pub(crate) fn leaf_paths(prefix: &str, document: &Document, paths: &mut Vec<String>) {
+    match document {
+        Document::Object(map) => {
+            for (key, value) in map {
+                let path: String = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                leaf_paths(&path, value, paths);
+            }
+        }
+        _ => paths.push(prefix.to_string()),
+    }
+}
@@ -0,0 +1,116 @@
+//! Warnings about configuration files that still load, but should be changed.
+//!
+//! Warnings are sent to the sink set with `ConfigurationBuilder::on_warning`,
+//! and dropped if there isn't one.
+
+use std::fmt::{self, Display};
+#[cfg(feature = "url")]
+use std::path::PathBuf;
+
+use crate::document::{self, Document};
+
+/// A function that receives warnings from a `ConfigurationBuilder`.
+pub(crate) type WarningSink = Box<dyn Fn(&Warning) + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+/// The representation of a warning from Cogwheel.
+pub enum Warning {
+    /// A deprecated key was used.
+    DeprecatedKey {
+        /// The dotted path of the key that was used, like `some_nest.some_number`.
+        key: String,
+        /// The dotted path of the key that should be used instead, if it was an alias.
+        replacement: Option<String>,
+        /// The message from `#[cogwheel(deprecated = "...")]`.
+        message: Option<String>,
+    },
+    /// A URL couldn't be fetched, so a cached copy of it was used, see `ConfigurationBuilder::cache_url_at`.
+    #[cfg(feature = "url")]
+    UnreachableUrl {
+        /// The URL that couldn't be fetched.
+        url: String,
+        /// The cached copy that was used instead.
+        cache: PathBuf,
+    },
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DeprecatedKey {
+                key,
+                replacement,
+                message,
+            } => {
+                write!(f, "`{key}` is deprecated")?;
+                if let Some(replacement) = replacement {
+                    write!(f, ", use `{replacement}` instead")?;
+                }
+                if let Some(message) = message {
+                    write!(f, " ({message})")?;
+                }
+                Ok(())
+            }
+            #[cfg(feature = "url")]
+            Self::UnreachableUrl { url, cache } => write!(
+                f,
+                "`{url}` is unreachable, using the cached copy at `{}`",
+                cache.display()
+            ),
+        }
+    }
+}
+
+/// An old name for a field, or a deprecated field.
+///
+/// This is generated by `#[derive(Configuration)]` from `#[cogwheel(alias = "...")]`
+/// and `#[cogwheel(deprecated = "...")]` on fields, see `Configuration::aliases`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alias {
+    /// The dotted path of the field.
+    pub key: String,
+    /// The dotted path of the old name, or `None` if the field itself is deprecated.
+    pub alias: Option<String>,
+    /// Why the old name (or the field) is deprecated.
+    pub deprecated: Option<String>,
+}
+
+impl Alias {
+    /// Makes this alias relative to the parent of a `#[cogwheel(nested)]` field called `parent`.
+    #[must_use]
+    pub fn nested_in(self, parent: &str) -> Self {
+        Self {
+            key: format!("{parent}.{}", self.key),
+            alias: self.alias.map(|alias: String| format!("{parent}.{alias}")),
+            deprecated: self.deprecated,
+        }
+    }
+
+    /// Moves the value at this alias to its field in `document`, warning `sink` if it was there.
+    /// If both are present, the field wins.
+    pub(crate) fn apply(&self, document: &mut Document, sink: &dyn Fn(&Warning)) {
+        let Some(alias) = &self.alias else {
+            if self.deprecated.is_some() && document::get_path(document, &self.key).is_some() {
+                sink(&Warning::DeprecatedKey {
+                    key: self.key.clone(),
+                    replacement: None,
+                    message: self.deprecated.clone(),
+                });
+            }
+            return;
+        };
+
+        let Some(value) = document::remove_path(document, alias) else {
+            return;
+        };
+        if document::get_path(document, &self.key).is_none() {
+            document::insert_path(document, &self.key, value);
+        }
+        sink(&Warning::DeprecatedKey {
+            key: alias.clone(),
+            replacement: Some(self.key.clone()),
+            message: self.deprecated.clone(),
+        });
+    }
+}
@@ -0,0 +1,285 @@
+//! Reading and writing XML files.
+//!
+//! The root element is `T`, whatever it's called, and elements map onto `T` like this:
+//! - An element with only text is that value, like `<some_int>4</some_int>`
+//! - An empty element, or one with only whitespace in it, is an empty string, table or list,
+//!   whichever `T` has there, like `<some_map/>`
+//! - An element with attributes or child elements is a table of them, like `<some_nest some_int="4"/>`
+//!   or `<some_nest><some_int>4</some_int></some_nest>`, with any text in it under `$text`
+//! - An element whose child elements are all `<item>`s is a list of them, like
+//!   `<some_list><item>1</item><item>2</item></some_list>`
+//! - Elements with the same name are a list of their values, like `<server>...</server>` written twice
+//!
+//! Text and attributes are strings, which are read as numbers or booleans where `T` has them.
+//! Namespace declarations, comments and processing instructions are ignored.
+//!
+//! Everything is written as elements under a `<configuration>` root, with lists written as `<item>`s.
+//! Empty tables and lists are written as empty elements, and `null`s are left out, so lists can't have any.
+
+use std::collections::HashSet;
+
+use quick_xml::{
+    escape,
+    events::{BytesStart, Event},
+    Reader,
+};
+
+use crate::{
+    document::{Document, Map},
+    Error,
+};
+
+/// The key that text in an element with attributes or child elements goes under.
+const TEXT_KEY: &str = "$text";
+
+/// A parsed element, before it's mapped onto a `Document`.
+struct Element {
+    name: String,
+    attributes: Map<String, Document>,
+    children: Vec<(String, Document)>,
+    text: String,
+}
+
+/// Parses an XML file into a `Document`.
+///
+/// # Errors
+/// This will fail if the file is malformed, or doesn't have exactly one root element.
+pub fn from_str(data: &str) -> Result<Document, Error> {
+    let mut reader: Reader<&[u8]> = Reader::from_str(data);
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root: Option<Document> = None;
+
+    loop {
+        let event: Event = reader.read_event().map_err(|error: quick_xml::Error| {
+            Error::XmlError(format!("at byte {}, {error}", reader.error_position()))
+        })?;
+
+        let element: Element = match event {
+            Event::Start(start) => {
+                stack.push(element(&start)?);
+                continue;
+            }
+            Event::Empty(start) => element(&start)?,
+            Event::End(_) => stack
+                .pop()
+                .ok_or_else(|| Error::XmlError("unexpected closing tag".to_string()))?,
+            Event::Text(text) => {
+                if let Some(parent) = stack.last_mut() {
+                    parent.text.push_str(&text.decode().map_err(xml_error)?);
+                }
+                continue;
+            }
+            Event::CData(data) => {
+                if let Some(parent) = stack.last_mut() {
+                    parent.text.push_str(&data.decode().map_err(xml_error)?);
+                }
+                continue;
+            }
+            Event::GeneralRef(reference) => {
+                if let Some(parent) = stack.last_mut() {
+                    let reference: String = format!("&{};", reference.decode().map_err(xml_error)?);
+                    parent
+                        .text
+                        .push_str(&escape::unescape(&reference).map_err(xml_error)?);
+                }
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+
+        match stack.last_mut() {
+            Some(parent) => {
+                let name: String = element.name.clone();
+                parent.children.push((name, element_to_document(element)));
+            }
+            None if root.is_some() => {
+                return Err(Error::XmlError(
+                    "expected only one root element".to_string(),
+                ))
+            }
+            None => root = Some(element_to_table(element)),
+        }
+    }
+
+    if let Some(element) = stack.last() {
+        return Err(Error::XmlError(format!("expected `</{}>`", element.name)));
+    }
+    root.ok_or_else(|| Error::XmlError("expected a root element".to_string()))
+}
+
+/// Converts any quick-xml error into an `Error`.
+fn xml_error(error: impl Into<quick_xml::Error>) -> Error {
+    Error::XmlError(error.into().to_string())
+}
+
+/// Reads an element's name and attributes, leaving out namespace declarations.
+fn element(start: &BytesStart) -> Result<Element, Error> {
+    let mut attributes: Map<String, Document> = Map::new();
+    for attribute in start.attributes() {
+        let attribute = attribute.map_err(xml_error)?;
+        let key: String = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+        if key == "xmlns" || key.starts_with("xmlns:") {
+            continue;
+        }
+        let value: String = attribute.unescape_value().map_err(xml_error)?.into_owned();
+        attributes.insert(key, value.into());
+    }
+
+    Ok(Element {
+        name: String::from_utf8_lossy(start.name().as_ref()).into_owned(),
+        attributes,
+        children: Vec::new(),
+        text: String::new(),
+    })
+}
+
+/// Maps an element onto its value, see the module documentation.
+fn element_to_document(element: Element) -> Document {
+    if element.attributes.is_empty() && element.children.is_empty() {
+        // Whitespace around child elements isn't text, even when there aren't any children.
+        if element.text.trim().is_empty() {
+            return Document::String(String::new());
+        }
+        return element.text.into();
+    }
+    if element.attributes.is_empty()
+        && element
+            .children
+            .iter()
+            .all(|(name, _): &(String, Document)| name == "item")
+    {
+        return Document::Array(
+            element
+                .children
+                .into_iter()
+                .map(|(_, value): (String, Document)| value)
+                .collect(),
+        );
+    }
+    element_to_table(element)
+}
+
+/// Maps an element's attributes, child elements and text onto a table,
+/// making lists of child elements with the same name.
+fn element_to_table(element: Element) -> Document {
+    let mut map: Map<String, Document> = element.attributes;
+    let mut repeated: HashSet<String> = HashSet::new();
+
+    for (name, value) in element.children {
+        match map.get_mut(&name) {
+            Some(Document::Array(values)) if repeated.contains(&name) => values.push(value),
+            Some(existing) => {
+                *existing = Document::Array(vec![existing.take(), value]);
+                repeated.insert(name);
+            }
+            None => {
+                map.insert(name, value);
+            }
+        }
+    }
+
+    let text: &str = element.text.trim();
+    if !text.is_empty() {
+        map.insert(TEXT_KEY.to_string(), text.into());
+    }
+    Document::Object(map)
+}
+
+/// Converts a `Document` into an XML file, see the module documentation.
+///
+/// # Errors
+/// This will fail if `document` isn't a table, or has a key that can't be an element name.
+pub fn to_string(document: &Document) -> Result<String, Error> {
+    if !document.is_object() {
+        return Err(Error::XmlError(
+            "only tables can be written as XML elements".to_string(),
+        ));
+    }
+
+    let mut output: String = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    write_element("configuration", document, 0, &mut output)?;
+    Ok(output)
+}
+
+/// Writes an element called `name` with the value `value` to `output`, indented `depth` times.
+fn write_element(
+    name: &str,
+    value: &Document,
+    depth: usize,
+    output: &mut String,
+) -> Result<(), Error> {
+    let valid: bool = name
+        .chars()
+        .next()
+        .is_some_and(|c: char| c.is_alphabetic() || c == '_' || c == ':')
+        && name
+            .chars()
+            .all(|c: char| c.is_alphanumeric() || matches!(c, '_' | ':' | '-' | '.'));
+    if !valid {
+        return Err(Error::XmlError(format!(
+            "`{name}` can't be written as an element name"
+        )));
+    }
+
+    let indent: String = "    ".repeat(depth);
+    if value.as_array().is_some_and(Vec::is_empty) || value.as_object().is_some_and(Map::is_empty) {
+        output.push_str(&indent);
+        output.push('<');
+        output.push_str(name);
+        output.push_str("/>\n");
+        return Ok(());
+    }
+
+    let (open, close): (String, String) = (format!("<{name}>"), format!("</{name}>\n"));
+    let text: String = match value {
+        Document::Null => return Ok(()),
+        Document::Bool(boolean) => boolean.to_string(),
+        Document::Number(number) => number.to_string(),
+        Document::String(string) => escape::escape(string.as_str()).into_owned(),
+        Document::Array(values) => {
+            output.push_str(&indent);
+            output.push_str(&open);
+            output.push('\n');
+            for value in values {
+                if value.is_null() {
+                    return Err(Error::XmlError(format!(
+                        "`{name}` has a `null` in it, which can't be written as an element"
+                    )));
+                }
+                write_element("item", value, depth + 1, output)?;
+            }
+            output.push_str(&indent);
+            output.push_str(&close);
+            return Ok(());
+        }
+        Document::Object(map) => {
+            output.push_str(&indent);
+            output.push_str(&open);
+            output.push('\n');
+            for (key, value) in map {
+                if key != TEXT_KEY {
+                    write_element(key, value, depth + 1, output)?;
+                    continue;
+                }
+                let text: String = match value {
+                    Document::String(string) => escape::escape(string.as_str()).into_owned(),
+                    value => value.to_string(),
+                };
+                output.push_str(&indent);
+                output.push_str("    ");
+                output.push_str(&text);
+                output.push('\n');
+            }
+            output.push_str(&indent);
+            output.push_str(&close);
+            return Ok(());
+        }
+    };
+
+    output.push_str(&indent);
+    output.push_str(&open);
+    output.push_str(&text);
+    output.push_str(&close);
+    Ok(())
+}
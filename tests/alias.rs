@@ -0,0 +1,76 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use cogwheel::{config::ConfigurationVariant, Configuration, Warning};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// A config struct with old names.
+struct SomeAliasedConfig {
+    #[cogwheel(alias = "some_text", deprecated = "renamed in 0.2")]
+    some_string: String,
+    #[cogwheel(deprecated = "does nothing anymore")]
+    some_bool: Option<bool>,
+    #[cogwheel(nested)]
+    some_nest: SomeAliasedNestedConfig,
+}
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// A nested config struct with old names.
+struct SomeAliasedNestedConfig {
+    #[cogwheel(alias = "some_number")]
+    some_int: i32,
+    #[cogwheel(default = "8080")]
+    some_unsigned: u32,
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn use_aliases() -> Result<()> {
+    let file: &str = r#"
+    some_text = "Hello, world!"
+    some_bool = true
+
+    [some_nest]
+    some_number = -4
+    "#;
+
+    let warnings: Arc<Mutex<Vec<Warning>>> = Arc::default();
+    let sink: Arc<Mutex<Vec<Warning>>> = Arc::clone(&warnings);
+    let config: SomeAliasedConfig = SomeAliasedConfig::builder()
+        .on_warning(move |warning: &Warning| sink.lock().unwrap().push(warning.clone()))
+        .use_str(file, ConfigurationVariant::Toml)?
+        .build()?;
+
+    assert_eq!(config.some_string, "Hello, world!");
+    assert_eq!(config.some_nest.some_int, -4_i32);
+    assert_eq!(config.some_nest.some_unsigned, 8080_u32);
+
+    let warnings: Vec<Warning> = warnings.lock().unwrap().clone();
+    assert_eq!(
+        warnings,
+        vec![
+            Warning::DeprecatedKey {
+                key: "some_text".to_string(),
+                replacement: Some("some_string".to_string()),
+                message: Some("renamed in 0.2".to_string()),
+            },
+            Warning::DeprecatedKey {
+                key: "some_bool".to_string(),
+                replacement: None,
+                message: Some("does nothing anymore".to_string()),
+            },
+            Warning::DeprecatedKey {
+                key: "some_nest.some_number".to_string(),
+                replacement: Some("some_nest.some_int".to_string()),
+                message: None,
+            },
+        ]
+    );
+    assert_eq!(
+        warnings[0].to_string(),
+        "`some_text` is deprecated, use `some_string` instead (renamed in 0.2)"
+    );
+
+    Ok(())
+}
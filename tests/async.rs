@@ -0,0 +1,50 @@
+#![cfg(feature = "tokio")]
+
+use std::env;
+
+use anyhow::Result;
+use cogwheel::{
+    config::{ConfigurationBuilder, ConfigurationVariant},
+    Configuration,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Configuration, Serialize, Deserialize, PartialEq)]
+/// Very barebones config struct.
+struct SomeBasicConfig {
+    some_string: String,
+    some_bool: bool,
+}
+
+#[tokio::test]
+#[cfg(feature = "toml")]
+async fn make_and_reload_async() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_async.toml");
+    let data: SomeBasicConfig = SomeBasicConfig {
+        some_string: "Hello, world!".to_string(),
+        some_bool: true,
+    };
+
+    let builder: ConfigurationBuilder<SomeBasicConfig> = SomeBasicConfig::builder()
+        .make_override_async(&path, &data, None)
+        .await?;
+    assert_eq!(builder.build_ref()?, &data);
+
+    tokio::fs::write(
+        &path,
+        "some_string = \"Goodbye, world!\"\nsome_bool = false\n",
+    )
+    .await?;
+    let builder: ConfigurationBuilder<SomeBasicConfig> = builder.reload_async().await?;
+    assert_eq!(builder.build_ref()?.some_string, "Goodbye, world!");
+
+    let builder: ConfigurationBuilder<SomeBasicConfig> = builder.save_async(&data).await?;
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .use_file_async(&path, ConfigurationVariant::Toml)
+        .await?
+        .build()?;
+    assert_eq!(config, builder.build()?);
+
+    tokio::fs::remove_file(path).await?;
+    Ok(())
+}
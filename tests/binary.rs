@@ -0,0 +1,78 @@
+#![cfg(any(feature = "msgpack", feature = "cbor"))]
+
+use std::{collections::BTreeMap, env, fs};
+
+use anyhow::Result;
+use cogwheel::{config::ConfigurationVariant, Configuration, Error, Format};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// Very barebones config struct.
+struct SomeBasicConfig {
+    some_string: String,
+    some_bool: bool,
+    some_list: Vec<i64>,
+    some_nest: SomeBasicNestedConfig,
+    some_map: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A very barebones nested config struct.
+struct SomeBasicNestedConfig {
+    some_int: i32,
+    some_unsigned: Option<u64>,
+}
+
+/// Writes a `SomeBasicConfig` as `variant` with `make_override`, then reads it back,
+/// both straight into the struct and through a `Document`, returning the bytes written.
+fn round_trip(variant: ConfigurationVariant, extension: &str) -> Result<Vec<u8>> {
+    let path = env::temp_dir().join(format!("cogwheel_round_trip.{extension}"));
+    let data: SomeBasicConfig = SomeBasicConfig {
+        some_string: "Hello, world!".to_string(),
+        some_bool: true,
+        some_list: vec![-1, 2, 3],
+        some_nest: SomeBasicNestedConfig {
+            some_int: -4,
+            some_unsigned: Some(u64::MAX),
+        },
+        some_map: BTreeMap::from([("ratio".to_string(), 2.5)]),
+    };
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .make_override(&path, &data, None)?
+        .build()?;
+    assert_eq!(config, data);
+
+    let output: Vec<u8> = fs::read(&path)?;
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .lenient()
+        .use_bytes(&output, variant)?
+        .build()?;
+    assert_eq!(config, data);
+
+    let result: Result<String, Error> = variant.serialize(&serde_json::json!({}));
+    assert!(matches!(result, Err(Error::BinaryFormat(_))));
+    fs::remove_file(path)?;
+
+    Ok(output)
+}
+
+#[test]
+#[cfg(feature = "msgpack")]
+fn msgpack_round_trip() -> Result<()> {
+    let output: Vec<u8> = round_trip(ConfigurationVariant::Msgpack, "msgpack")?;
+    // A fixmap of five fields.
+    assert_eq!(output[0], 0x85);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "cbor")]
+fn cbor_round_trip() -> Result<()> {
+    let output: Vec<u8> = round_trip(ConfigurationVariant::Cbor, "cbor")?;
+    // A map of five entries.
+    assert_eq!(output[0], 0xA5);
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use std::io::Cursor;
+
+use anyhow::Result;
+use cogwheel::{config::ConfigurationVariant, Configuration, Error};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// Very barebones config struct.
+struct SomeBasicConfig {
+    some_string: String,
+    some_bool: bool,
+}
+
+const FILE: &str = "some_string = \"Hello, world!\"\nsome_bool = true\n";
+
+#[test]
+#[cfg(feature = "toml")]
+fn use_bytes_with_bom() -> Result<()> {
+    let utf8: Vec<u8> = [&[0xEF, 0xBB, 0xBF], FILE.as_bytes()].concat();
+    let utf16: Vec<u8> = [0xFF, 0xFE]
+        .into_iter()
+        .chain(FILE.encode_utf16().flat_map(u16::to_le_bytes))
+        .collect();
+
+    for data in [FILE.as_bytes(), &utf8, &utf16] {
+        let config: SomeBasicConfig = SomeBasicConfig::builder()
+            .use_bytes(data, ConfigurationVariant::Toml)?
+            .build()?;
+
+        assert_eq!(config.some_string, "Hello, world!");
+        assert!(config.some_bool);
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn use_reader() -> Result<()> {
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .use_reader(Cursor::new(FILE), ConfigurationVariant::Toml)?
+        .build()?;
+
+    assert_eq!(config.some_string, "Hello, world!");
+    assert!(config.some_bool);
+
+    let result: Result<_, Error> =
+        SomeBasicConfig::builder().use_bytes(&[0xC3, 0x28], ConfigurationVariant::Toml);
+    assert!(matches!(result, Err(Error::InvalidEncoding)));
+
+    Ok(())
+}
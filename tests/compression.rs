@@ -0,0 +1,64 @@
+#![cfg(all(feature = "compression", feature = "toml"))]
+
+use std::{env, fs, io::Write};
+
+use anyhow::Result;
+use cogwheel::{
+    config::ConfigurationVariant,
+    source::{FileSource, Source},
+    Configuration,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// Very barebones config struct.
+struct SomeBasicConfig {
+    some_string: String,
+    some_bool: bool,
+}
+
+const FILE: &str = "some_string = \"Hello, world!\"\nsome_bool = true\n";
+
+#[test]
+fn use_compressed_file() -> Result<()> {
+    let gzip = env::temp_dir().join("cogwheel_compressed.toml.gz");
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(FILE.as_bytes())?;
+    fs::write(&gzip, encoder.finish()?)?;
+
+    let zstd = env::temp_dir().join("cogwheel_compressed.TOML.ZST");
+    fs::write(&zstd, zstd::encode_all(FILE.as_bytes(), 0)?)?;
+
+    for path in [&gzip, &zstd] {
+        let config: SomeBasicConfig = SomeBasicConfig::builder().use_file_guessed(path)?.build()?;
+        assert_eq!(config.some_string, "Hello, world!");
+        assert!(config.some_bool);
+
+        let document = FileSource::new(path, ConfigurationVariant::Toml).load()?;
+        assert_eq!(document["some_bool"], true);
+        fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn make_compressed_file() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_make_compressed.toml.zst");
+    let data: SomeBasicConfig = SomeBasicConfig {
+        some_string: "Hello, world!".to_string(),
+        some_bool: true,
+    };
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .make_override(&path, &data, None)?
+        .build()?;
+    assert_eq!(config, data);
+    assert_eq!(
+        zstd::decode_all(fs::read(&path)?.as_slice())?,
+        FILE.as_bytes()
+    );
+    fs::remove_file(path)?;
+
+    Ok(())
+}
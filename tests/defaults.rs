@@ -0,0 +1,122 @@
+use anyhow::Result;
+use cogwheel::{config::ConfigurationVariant, secret::Secret, Configuration};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// Very barebones config struct.
+struct SomeBasicConfig {
+    some_string: String,
+    some_bool: bool,
+    some_nest: SomeBasicNestedConfig,
+}
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// A very barebones nested config struct.
+struct SomeBasicNestedConfig {
+    some_int: i32,
+    some_unsigned: u32,
+}
+
+#[derive(Debug, Configuration, Serialize, Deserialize)]
+/// A config struct with per-field defaults.
+struct SomeDefaultedConfig {
+    some_host: String,
+    #[cogwheel(default = "8080")]
+    some_port: u16,
+    #[serde(rename = "someName")]
+    #[cogwheel(default_fn = default_name)]
+    some_name: String,
+}
+
+fn default_name() -> String {
+    "cogwheel".to_string()
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn lenient_configuration() -> Result<()> {
+    let file: &str = r"
+    some_bool = true
+
+    [some_nest]
+    some_int = -4
+    ";
+
+    assert!(SomeBasicConfig::builder()
+        .use_str(file, ConfigurationVariant::Toml)
+        .is_err());
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .lenient()
+        .use_str(file, ConfigurationVariant::Toml)?
+        .build()?;
+
+    assert_eq!(config.some_string, "");
+    assert!(config.some_bool);
+    assert_eq!(config.some_nest.some_int, -4_i32);
+    assert_eq!(config.some_nest.some_unsigned, 0_u32);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn field_defaults() -> Result<()> {
+    let config: SomeDefaultedConfig = SomeDefaultedConfig::builder()
+        .use_str(r#"some_host = "localhost""#, ConfigurationVariant::Toml)?
+        .build()?;
+
+    assert_eq!(config.some_host, "localhost");
+    assert_eq!(config.some_port, 8080_u16);
+    assert_eq!(config.some_name, "cogwheel");
+
+    let config: SomeDefaultedConfig = SomeDefaultedConfig::builder()
+        .use_str(
+            "some_host = \"localhost\"\nsome_port = 80\nsomeName = \"gear\"",
+            ConfigurationVariant::Toml,
+        )?
+        .build()?;
+
+    assert_eq!(config.some_port, 80_u16);
+    assert_eq!(config.some_name, "gear");
+
+    Ok(())
+}
+
+#[derive(Debug, Configuration, Serialize, Deserialize)]
+/// A config struct with secret defaults.
+struct SomeSecretConfig {
+    some_user: String,
+    some_password: Secret<String>,
+    #[cogwheel(default_fn = default_token)]
+    some_token: Secret<String>,
+}
+
+impl Default for SomeSecretConfig {
+    fn default() -> Self {
+        Self {
+            some_user: "admin".to_string(),
+            some_password: Secret::new("hunter2".to_string()),
+            some_token: Secret::new(String::new()),
+        }
+    }
+}
+
+fn default_token() -> Secret<String> {
+    Secret::new("abc123".to_string())
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn secret_defaults() -> Result<()> {
+    let config: SomeSecretConfig = SomeSecretConfig::builder()
+        .lenient()
+        .use_str(r#"some_user = "root""#, ConfigurationVariant::Toml)?
+        .build()?;
+
+    assert_eq!(config.some_user, "root");
+    assert_eq!(config.some_password.expose(), "hunter2");
+    assert_eq!(config.some_token.expose(), "abc123");
+
+    Ok(())
+}
@@ -97,3 +97,61 @@ fn deserialize_configuration_yaml() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg(feature = "ron")]
+fn deserialize_configuration_ron() -> Result<()> {
+    let file: &str = r#"
+    (
+        some_string: "Hello, world!",
+        some_bool: true,
+        some_nest: (
+            some_int: -4,
+            some_float: 2.5,
+            some_unsigned: 2147483648,
+        ),
+    )
+    "#;
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .use_str(file, ConfigurationVariant::Ron)?
+        .build()?;
+
+    assert_eq!(config.some_string, "Hello, world!");
+    assert!(config.some_bool);
+    assert_eq!(config.some_nest.some_int, -4_i32);
+    assert_eq!(config.some_nest.some_float, 2.5);
+    assert_eq!(config.some_nest.some_unsigned, 2_147_483_648_u32);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "json5")]
+fn deserialize_configuration_json5() -> Result<()> {
+    let file: &str = r#"
+    // Comments like these are what JSONC adds.
+    {
+        some_string: 'Hello, world!',
+        "some_bool": true,
+        /* Trailing commas are fine too. */
+        some_nest: {
+            some_int: -4,
+            some_float: 2.5,
+            some_unsigned: 0x80000000,
+        },
+    }
+    "#;
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .use_str(file, ConfigurationVariant::Json5)?
+        .build()?;
+
+    assert_eq!(config.some_string, "Hello, world!");
+    assert!(config.some_bool);
+    assert_eq!(config.some_nest.some_int, -4_i32);
+    assert_eq!(config.some_nest.some_float, 2.5);
+    assert_eq!(config.some_nest.some_unsigned, 2_147_483_648_u32);
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use cogwheel::{
+    diff::{Change, Diff},
+    Configuration, Secret,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, Clone, Default, Configuration, Serialize, Deserialize)]
+/// Very barebones config struct.
+struct SomeBasicConfig {
+    some_string: String,
+    some_bool: bool,
+    some_nest: SomeBasicNestedConfig,
+    some_map: BTreeMap<String, i32>,
+}
+
+#[derive(Debug, Clone, Default, Configuration, Serialize, Deserialize)]
+/// A config struct with a secret.
+struct SomeSecretConfig {
+    some_user: String,
+    some_password: Secret<String>,
+}
+
+#[derive(Debug, Clone, Default, Configuration, Serialize, Deserialize)]
+/// A very barebones nested config struct.
+struct SomeBasicNestedConfig {
+    some_int: i32,
+    some_unsigned: u32,
+}
+
+#[test]
+fn diff_configurations() -> Result<()> {
+    let old: SomeBasicConfig = SomeBasicConfig {
+        some_string: "Hello, world!".to_string(),
+        some_bool: true,
+        some_nest: SomeBasicNestedConfig {
+            some_int: -4,
+            some_unsigned: 2_147_483_648,
+        },
+        some_map: BTreeMap::from([("a".to_string(), 1)]),
+    };
+    let mut new: SomeBasicConfig = old.clone();
+    new.some_nest.some_int = 4;
+    new.some_map = BTreeMap::from([("b".to_string(), 2)]);
+
+    assert!(cogwheel::diff(&old, &old)?.is_empty());
+
+    let diff: Diff = cogwheel::diff(&old, &new)?;
+    assert_eq!(
+        diff.changes,
+        vec![
+            Change {
+                path: "some_nest.some_int".to_string(),
+                old: Some(json!(-4)),
+                new: Some(json!(4)),
+            },
+            Change {
+                path: "some_map.a".to_string(),
+                old: Some(json!(1)),
+                new: None,
+            },
+            Change {
+                path: "some_map.b".to_string(),
+                old: None,
+                new: Some(json!(2)),
+            },
+        ]
+    );
+    assert_eq!(
+        diff.to_string(),
+        "~ some_nest.some_int: -4 -> 4\n- some_map.a: 1\n+ some_map.b: 2"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn diff_secrets() -> Result<()> {
+    let old: SomeSecretConfig = SomeSecretConfig {
+        some_user: "admin".to_string(),
+        some_password: "hunter2".to_string().into(),
+    };
+    let mut new: SomeSecretConfig = old.clone();
+    assert!(cogwheel::diff(&old, &new)?.is_empty());
+
+    new.some_password = "hunter3".to_string().into();
+    let diff: Diff = cogwheel::diff(&old, &new)?;
+    assert_eq!(
+        diff.changes,
+        vec![Change {
+            path: "some_password".to_string(),
+            old: Some(json!("[REDACTED]")),
+            new: Some(json!("[REDACTED]")),
+        }]
+    );
+    assert!(!diff.to_string().contains("hunter"));
+
+    Ok(())
+}
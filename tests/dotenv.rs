@@ -0,0 +1,90 @@
+#![cfg(feature = "dotenv")]
+
+use std::{env, fs};
+
+use anyhow::Result;
+use cogwheel::{config::ConfigurationVariant, Configuration, Error};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// Very barebones config struct.
+struct SomeBasicConfig {
+    some_string: String,
+    some_bool: bool,
+    some_nest: SomeBasicNestedConfig,
+}
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A very barebones nested config struct.
+struct SomeBasicNestedConfig {
+    some_int: i32,
+    some_password: String,
+    some_unsigned: Option<u32>,
+}
+
+#[test]
+fn deserialize_dotenv() -> Result<()> {
+    let file: &str = r#"
+# Comments and blank lines are skipped.
+export SOME_STRING="Hello,
+\"world\"!"
+SOME_BOOL=true # so are comments after values
+SOME_NEST__SOME_INT = -4
+SOME_NEST__SOME_PASSWORD='1234$\n'
+"#;
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .use_str(file, ConfigurationVariant::Dotenv)?
+        .build()?;
+
+    assert_eq!(config.some_string, "Hello,\n\"world\"!");
+    assert!(config.some_bool);
+    assert_eq!(config.some_nest.some_int, -4_i32);
+    assert_eq!(config.some_nest.some_password, "1234$\\n");
+    assert_eq!(config.some_nest.some_unsigned, None);
+
+    // Unquoted values are read as whatever their field is.
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .use_str(
+            "SOME_STRING=123\nSOME_BOOL=false\nSOME_NEST__SOME_INT=7\nSOME_NEST__SOME_PASSWORD=true",
+            ConfigurationVariant::Dotenv,
+        )?
+        .build()?;
+    assert_eq!(config.some_string, "123");
+    assert!(!config.some_bool);
+    assert_eq!(config.some_nest.some_int, 7_i32);
+    assert_eq!(config.some_nest.some_password, "true");
+
+    let result: Result<_, Error> =
+        SomeBasicConfig::builder().use_str("SOME_STRING=\"unclosed", ConfigurationVariant::Dotenv);
+    assert!(matches!(result, Err(Error::InvalidDotenv(1))));
+
+    Ok(())
+}
+
+#[test]
+fn dotenv_round_trip() -> Result<()> {
+    let path = env::temp_dir().join(".env.cogwheel");
+    let data: SomeBasicConfig = SomeBasicConfig {
+        some_string: "Hello,\n\"world\"!".to_string(),
+        some_bool: true,
+        some_nest: SomeBasicNestedConfig {
+            some_int: -4,
+            some_password: "1234".to_string(),
+            some_unsigned: Some(2_147_483_648),
+        },
+    };
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .make_override(&path, &data, None)?
+        .build()?;
+    assert_eq!(config, data);
+    assert_eq!(
+        fs::read_to_string(&path)?,
+        "SOME_STRING=\"Hello,\\n\\\"world\\\"!\"\nSOME_BOOL=true\nSOME_NEST__SOME_INT=-4\n\
+         SOME_NEST__SOME_PASSWORD=\"1234\"\nSOME_NEST__SOME_UNSIGNED=2147483648\n"
+    );
+    fs::remove_file(path)?;
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+#![cfg(feature = "encryption")]
+
+use anyhow::Result;
+use cogwheel::{config::ConfigurationVariant, encryption::Key, Configuration, Error, Secret};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// A config struct with an encrypted value in it.
+struct SomeEncryptedConfig {
+    some_user: String,
+    some_password: Secret<String>,
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn decrypt_configuration() -> Result<()> {
+    let key: Key = Key::generate();
+    let file: String = format!(
+        "some_user = \"admin\"\nsome_password = \"{}\"\n",
+        key.encrypt("hunter2")?
+    );
+
+    let config: SomeEncryptedConfig = SomeEncryptedConfig::builder()
+        .decrypt_with(Key::from_base64(&key.to_base64())?)
+        .use_str(&file, ConfigurationVariant::Toml)?
+        .build()?;
+
+    assert!(!file.contains("hunter2"));
+    assert_eq!(config.some_user, "admin");
+    assert_eq!(config.some_password.expose(), "hunter2");
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn decrypt_with_wrong_key() -> Result<()> {
+    let file: String = format!(
+        "some_user = \"admin\"\nsome_password = \"{}\"\n",
+        Key::generate().encrypt("hunter2")?
+    );
+
+    let result: Result<SomeEncryptedConfig, Error> = SomeEncryptedConfig::builder()
+        .decrypt_with(Key::generate())
+        .use_str(&file, ConfigurationVariant::Toml)
+        .and_then(cogwheel::config::ConfigurationBuilder::build);
+
+    assert!(matches!(result, Err(Error::DecryptionError)));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn save_encrypts_again() -> Result<()> {
+    let path = std::env::temp_dir().join("cogwheel_encryption_save.toml");
+    let key: Key = Key::generate();
+    std::fs::write(
+        &path,
+        format!(
+            "some_user = \"{}\"\nsome_password = \"{}\"\n",
+            key.encrypt("admin")?,
+            key.encrypt("hunter2")?
+        ),
+    )?;
+
+    for expose in [false, true] {
+        let mut builder =
+            SomeEncryptedConfig::builder().decrypt_with(Key::from_base64(&key.to_base64())?);
+        if expose {
+            builder = builder.expose_secrets();
+        }
+        let builder = builder.use_file(&path, ConfigurationVariant::Toml)?;
+        let mut config: SomeEncryptedConfig = SomeEncryptedConfig::builder()
+            .decrypt_with(Key::from_base64(&key.to_base64())?)
+            .use_file(&path, ConfigurationVariant::Toml)?
+            .build()?;
+        config.some_user = "root".to_string();
+        builder.save(&config)?;
+
+        let file: String = std::fs::read_to_string(&path)?;
+        assert!(!file.contains("root"));
+        assert!(!file.contains("hunter2"));
+        assert!(!file.contains("REDACTED"));
+
+        let config: SomeEncryptedConfig = SomeEncryptedConfig::builder()
+            .decrypt_with(Key::from_base64(&key.to_base64())?)
+            .use_file(&path, ConfigurationVariant::Toml)?
+            .build()?;
+        assert_eq!(config.some_user, "root");
+        assert_eq!(config.some_password.expose(), "hunter2");
+    }
+    std::fs::remove_file(path)?;
+
+    Ok(())
+}
@@ -0,0 +1,243 @@
+use std::{env, fs};
+
+use anyhow::Result;
+use cogwheel::{
+    config::ConfigurationVariant,
+    document::{Document, Map},
+    Configuration, Error, Format,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A flat config struct, for flat formats.
+struct SomeFlatConfig {
+    some_string: String,
+    some_other_string: Option<String>,
+}
+
+/// `key=value` lines.
+struct KeyValue;
+
+impl Format for KeyValue {
+    fn name(&self) -> &'static str {
+        "key-value"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["kv", "toml"]
+    }
+
+    fn sniff(&self, data: &str) -> bool {
+        data.lines().all(|line: &str| line.contains('='))
+    }
+
+    fn parse(&self, data: &str) -> Result<Document, Error> {
+        let map: Map<String, Document> = data
+            .lines()
+            .filter_map(|line: &str| line.split_once('='))
+            .map(|(key, value): (&str, &str)| (key.to_string(), value.into()))
+            .collect();
+        Ok(Document::Object(map))
+    }
+
+    fn serialize(&self, document: &Document) -> Result<String, Error> {
+        let map: &Map<String, Document> = document.as_object().ok_or_else(|| {
+            Error::FormatError(self.name().to_string(), "expected a table".into())
+        })?;
+        Ok(map
+            .iter()
+            .filter_map(|(key, value): (&String, &Document)| {
+                Some(format!("{key}={}\n", value.as_str()?))
+            })
+            .collect())
+    }
+}
+
+#[test]
+fn custom_format() -> Result<()> {
+    let config: SomeFlatConfig = SomeFlatConfig::builder()
+        .use_str("some_string=Hello, world!", KeyValue)?
+        .build()?;
+    assert_eq!(config.some_string, "Hello, world!");
+    assert_eq!(config.some_other_string, None);
+
+    let path = env::temp_dir().join("cogwheel_format.kv");
+    let data: SomeFlatConfig = SomeFlatConfig {
+        some_string: "Hello, world!".to_string(),
+        some_other_string: Some("Goodbye, world!".to_string()),
+    };
+    let builder = SomeFlatConfig::builder()
+        .register_format(KeyValue)
+        .make_override(&path, &data, None)?;
+    assert_eq!(
+        fs::read_to_string(&path)?,
+        "some_string=Hello, world!\nsome_other_string=Goodbye, world!\n"
+    );
+    assert_eq!(builder.build()?, data);
+
+    let result: Result<_, Error> = SomeFlatConfig::builder().make_override(&path, &data, None);
+    assert!(matches!(result, Err(Error::CouldNotGuess)));
+
+    let config: SomeFlatConfig = SomeFlatConfig::builder()
+        .make_override(&path, &data, KeyValue)?
+        .build()?;
+    assert_eq!(config, data);
+    fs::remove_file(path)?;
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn registered_before_builtin() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_format_registered.toml");
+    fs::write(&path, "some_string=Hello, world!")?;
+
+    let result: Result<_, Error> = SomeFlatConfig::builder().use_file_guessed(&path);
+    assert!(matches!(result, Err(Error::TomlDeError(_))));
+
+    let config: SomeFlatConfig = SomeFlatConfig::builder()
+        .register_format(KeyValue)
+        .use_file_guessed(&path)?
+        .build()?;
+    assert_eq!(config.some_string, "Hello, world!");
+    fs::remove_file(path)?;
+
+    Ok(())
+}
+
+#[test]
+#[cfg(all(feature = "json", feature = "toml"))]
+fn sniff_format() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_format_sniffed");
+
+    fs::write(&path, r#"{ "some_string": "Hello, world!" }"#)?;
+    let config: SomeFlatConfig = SomeFlatConfig::builder().use_file_guessed(&path)?.build()?;
+    assert_eq!(config.some_string, "Hello, world!");
+
+    fs::write(&path, "# comment\nsome_string = \"Hello, world!\"")?;
+    let config: SomeFlatConfig = SomeFlatConfig::builder().use_file_guessed(&path)?.build()?;
+    assert_eq!(config.some_string, "Hello, world!");
+    fs::remove_file(path)?;
+
+    assert_eq!(ConfigurationVariant::Toml.extensions(), ["toml"]);
+    Ok(())
+}
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A config struct with a bit of everything, for round trips.
+struct SomeNestedConfig {
+    some_string: String,
+    some_bool: bool,
+    some_list: Vec<i32>,
+    some_nest: SomeFlatConfig,
+}
+
+/// Writes a `SomeNestedConfig` to a file ending in `extension`, then reads it back,
+/// both straight into the struct and through a `Document`.
+#[allow(dead_code)]
+fn round_trip(extension: &str) -> Result<String> {
+    let path = env::temp_dir().join(format!("cogwheel_round_trip.{extension}"));
+    let data: SomeNestedConfig = SomeNestedConfig {
+        some_string: "Hello, \"world\"!".to_string(),
+        some_bool: true,
+        some_list: vec![1, -2, 3],
+        some_nest: SomeFlatConfig {
+            some_string: "Goodbye, world!".to_string(),
+            some_other_string: None,
+        },
+    };
+
+    let config: SomeNestedConfig = SomeNestedConfig::builder()
+        .make_override(&path, &data, None)?
+        .build()?;
+    assert_eq!(config, data);
+
+    let config: SomeNestedConfig = SomeNestedConfig::builder()
+        .lenient()
+        .use_file_guessed(&path)?
+        .build()?;
+    assert_eq!(config, data);
+
+    let output: String = fs::read_to_string(&path)?;
+    fs::remove_file(path)?;
+    Ok(output)
+}
+
+#[test]
+#[cfg(feature = "ron")]
+fn ron_round_trip() -> Result<()> {
+    let output: String = round_trip("ron")?;
+    assert!(output.starts_with('('));
+
+    Ok(())
+}
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A config struct with enums and named structs, for RON.
+struct SomeEnumConfig {
+    some_mode: SomeMode,
+    some_modes: Vec<SomeMode>,
+    some_nest: SomeFlatConfig,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+/// An enum with every kind of variant.
+enum SomeMode {
+    #[default]
+    Fast,
+    Slow(u32),
+    Limited {
+        some_limit: u32,
+    },
+}
+
+#[test]
+#[cfg(feature = "ron")]
+fn ron_enums() -> Result<()> {
+    let file: &str = r#"
+SomeEnumConfig(
+    some_mode: Slow(3),
+    some_modes: [Fast, Limited(some_limit: 2)],
+    some_nest: SomeFlatConfig(some_string: "Hello, world!"),
+)
+"#;
+    let data: SomeEnumConfig = SomeEnumConfig {
+        some_mode: SomeMode::Slow(3),
+        some_modes: vec![SomeMode::Fast, SomeMode::Limited { some_limit: 2 }],
+        some_nest: SomeFlatConfig {
+            some_string: "Hello, world!".to_string(),
+            some_other_string: None,
+        },
+    };
+
+    // `lenient` makes the file go through a `Document`.
+    for lenient in [false, true] {
+        let mut builder = SomeEnumConfig::builder();
+        if lenient {
+            builder = builder.lenient();
+        }
+        let config: SomeEnumConfig = builder.use_str(file, ConfigurationVariant::Ron)?.build()?;
+        assert_eq!(config, data);
+    }
+
+    let path = env::temp_dir().join("cogwheel_enums.ron");
+    SomeEnumConfig::builder().make_override(&path, &data, None)?;
+    let config: SomeEnumConfig = SomeEnumConfig::builder()
+        .lenient()
+        .use_file(&path, ConfigurationVariant::Ron)?
+        .build()?;
+    assert_eq!(config, data);
+    fs::remove_file(path)?;
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "json5")]
+fn json5_round_trip() -> Result<()> {
+    let output: String = round_trip("jsonc")?;
+    assert!(json5::from_str::<serde_json::Value>(&output).is_ok());
+
+    Ok(())
+}
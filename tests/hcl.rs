@@ -0,0 +1,158 @@
+#![cfg(feature = "hcl")]
+
+use std::{collections::BTreeMap, env, fs};
+
+use anyhow::Result;
+use cogwheel::{config::ConfigurationVariant, Configuration, Error};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// Very barebones config struct.
+struct SomeBasicConfig {
+    some_string: String,
+    some_list: Vec<i64>,
+    some_nest: SomeBasicNestedConfig,
+    servers: BTreeMap<String, SomeServerConfig>,
+}
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A very barebones nested config struct.
+struct SomeBasicNestedConfig {
+    some_int: i32,
+    some_unsigned: Option<u32>,
+    some_map: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A config struct that's used in labelled blocks.
+struct SomeServerConfig {
+    host: String,
+    port: u16,
+}
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A config struct with two labels on its blocks.
+struct SomeResourceConfig {
+    resource: BTreeMap<String, BTreeMap<String, BTreeMap<String, String>>>,
+}
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A config struct with a key that can't be an HCL identifier.
+struct SomeInvalidConfig {
+    #[serde(rename = "with spaces")]
+    with_spaces: String,
+}
+
+#[test]
+fn deserialize_hcl() -> Result<()> {
+    let file: &str = r#"
+# Comments are skipped.
+some_string = "Hello, world!"
+some_list = [1, 2, 3]
+
+some_nest {
+    some_int = -4
+    some_map = {
+        "with spaces" = "value"
+    }
+}
+
+servers "primary" {
+    host = "localhost"
+    port = 8080
+}
+
+servers "backup" {
+    host = "example.com"
+    port = 443
+}
+"#;
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .use_str(file, ConfigurationVariant::Hcl)?
+        .build()?;
+
+    assert_eq!(config.some_string, "Hello, world!");
+    assert_eq!(config.some_list, [1, 2, 3]);
+    assert_eq!(config.some_nest.some_int, -4);
+    assert_eq!(config.some_nest.some_unsigned, None);
+    assert_eq!(config.some_nest.some_map["with spaces"], "value");
+    assert_eq!(config.servers.len(), 2);
+    assert_eq!(config.servers["primary"].port, 8080);
+    assert_eq!(config.servers["backup"].host, "example.com");
+
+    Ok(())
+}
+
+#[test]
+fn hcl_round_trip() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_round_trip.hcl");
+    let data: SomeBasicConfig = SomeBasicConfig {
+        some_string: "Hello, \"world\"!".to_string(),
+        some_list: vec![-1, 2],
+        some_nest: SomeBasicNestedConfig {
+            some_int: -4,
+            some_unsigned: Some(4),
+            some_map: BTreeMap::from([("1 two".to_string(), "three".to_string())]),
+        },
+        servers: BTreeMap::from([(
+            "primary".to_string(),
+            SomeServerConfig {
+                host: "localhost".to_string(),
+                port: 8080,
+            },
+        )]),
+    };
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .make_override(&path, &data, None)?
+        .build()?;
+    assert_eq!(config, data);
+
+    let output: String = fs::read_to_string(&path)?;
+    assert!(output.contains("some_nest {"));
+    assert!(output.contains("servers \"primary\" {"));
+    assert!(output.contains("some_map = {"));
+    fs::remove_file(path)?;
+
+    Ok(())
+}
+
+#[test]
+fn labelled_round_trip() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_labelled_round_trip.hcl");
+    let file: &str = r#"
+resource "a" "b" {}
+
+resource "a" "c" {
+  name = "Hello, world!"
+}
+"#;
+    fs::write(&path, file)?;
+
+    let config: SomeResourceConfig = SomeResourceConfig::builder()
+        .use_file(&path, ConfigurationVariant::Hcl)?
+        .build()?;
+    assert_eq!(config.resource["a"]["b"].len(), 0);
+    assert_eq!(config.resource["a"]["c"]["name"], "Hello, world!");
+
+    let saved: SomeResourceConfig = SomeResourceConfig::builder()
+        .make_override(&path, &config, None)?
+        .build()?;
+    assert_eq!(saved, config);
+
+    let output: String = fs::read_to_string(&path)?;
+    assert!(output.contains("resource \"a\" \"b\" {}"));
+    assert!(output.contains("resource \"a\" \"c\" {"));
+    fs::remove_file(path)?;
+
+    Ok(())
+}
+
+#[test]
+fn invalid_identifier() {
+    let path = env::temp_dir().join("cogwheel_invalid_identifier.hcl");
+    let result =
+        SomeInvalidConfig::builder().make_override(&path, &SomeInvalidConfig::default(), None);
+    assert!(matches!(result, Err(Error::HclError(_))));
+}
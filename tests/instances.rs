@@ -0,0 +1,75 @@
+#![cfg(all(feature = "toml", feature = "json"))]
+
+use std::{collections::BTreeMap, env, fs, path::PathBuf};
+
+use anyhow::Result;
+use cogwheel::{Configuration, Error};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// One site's config struct.
+struct SomeSiteConfig {
+    host: String,
+    port: u16,
+    tls: bool,
+}
+
+/// Creates an empty directory for a test called `name`.
+fn directory(name: &str) -> Result<PathBuf> {
+    let path: PathBuf = env::temp_dir().join(format!("cogwheel_instances_{name}"));
+    if path.exists() {
+        fs::remove_dir_all(&path)?;
+    }
+    fs::create_dir(&path)?;
+    Ok(path)
+}
+
+#[test]
+fn use_instances() -> Result<()> {
+    let path: PathBuf = directory("layered")?;
+    fs::write(path.join("_defaults.toml"), "port = 443\ntls = true\n")?;
+    fs::write(path.join("example.com.toml"), "host = \"example.com\"\n")?;
+    fs::write(path.join("example.org.toml"), "host = \"example.org\"\n")?;
+    fs::write(
+        path.join("local.json"),
+        r#"{ "host": "localhost", "port": 8080, "tls": false }"#,
+    )?;
+    fs::write(path.join("README"), "Not a site.")?;
+    fs::create_dir(path.join("old"))?;
+
+    let sites: BTreeMap<String, SomeSiteConfig> = SomeSiteConfig::builder().use_instances(&path)?;
+    assert_eq!(
+        sites.keys().collect::<Vec<_>>(),
+        ["example.com", "example.org", "local"]
+    );
+    assert_eq!(sites["example.org"].host, "example.org");
+    assert_eq!(
+        sites["example.com"],
+        SomeSiteConfig {
+            host: "example.com".to_string(),
+            port: 443,
+            tls: true,
+        }
+    );
+    assert_eq!(sites["local"].port, 8080);
+    assert!(!sites["local"].tls);
+    fs::remove_dir_all(path)?;
+
+    Ok(())
+}
+
+#[test]
+fn invalid_instances() -> Result<()> {
+    let path: PathBuf = directory("invalid")?;
+    fs::write(path.join("example.toml"), "host = \"example.com\"\n")?;
+
+    let result: Result<_, Error> = SomeSiteConfig::builder().use_instances(&path);
+    assert!(matches!(result, Err(Error::SourceError(label, _)) if label.ends_with("example.toml")));
+
+    fs::write(path.join("example.json"), "{}")?;
+    let result: Result<_, Error> = SomeSiteConfig::builder().use_instances(&path);
+    assert!(matches!(result, Err(Error::DuplicateInstance(name)) if name == "example"));
+    fs::remove_dir_all(path)?;
+
+    Ok(())
+}
@@ -0,0 +1,160 @@
+#![cfg(feature = "kdl")]
+
+use std::{collections::BTreeMap, env, fs};
+
+use anyhow::Result;
+use cogwheel::{config::ConfigurationVariant, Configuration, Error};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// Very barebones config struct.
+struct SomeBasicConfig {
+    some_string: String,
+    some_bool: bool,
+    some_list: Vec<i64>,
+    some_nest: SomeBasicNestedConfig,
+    servers: Vec<SomeServerConfig>,
+    some_map: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A very barebones nested config struct.
+struct SomeBasicNestedConfig {
+    some_int: i32,
+    some_float: f32,
+    some_unsigned: Option<u32>,
+}
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A config struct that's repeated.
+struct SomeServerConfig {
+    host: String,
+    port: u16,
+}
+
+#[test]
+fn deserialize_kdl() -> Result<()> {
+    let file: &str = r##"
+// Comments are skipped, /* even */ inline ones.
+some_string "Hello, \u{1f30d}!\n"
+some_bool true
+some_list 1 0x10 -0b11 1_000
+/-some_list "commented out"
+(nested)some_nest some_int=-4 {
+    some_float 2.5
+}
+servers {
+    - host=localhost port=8080
+    - host=#"example.com"# port=443
+}
+some_map {
+    "with spaces" r"raw \string"; bare bare-string
+}
+"##;
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .use_str(file, ConfigurationVariant::Kdl)?
+        .build()?;
+
+    assert_eq!(config.some_string, "Hello, \u{1f30d}!\n");
+    assert!(config.some_bool);
+    assert_eq!(config.some_list, [1, 16, -3, 1000]);
+    assert_eq!(config.some_nest.some_int, -4_i32);
+    assert_eq!(config.some_nest.some_float, 2.5);
+    assert_eq!(config.some_nest.some_unsigned, None);
+    assert_eq!(config.servers.len(), 2);
+    assert_eq!(config.servers[1].host, "example.com");
+    assert_eq!(config.some_map["with spaces"], "raw \\string");
+    assert_eq!(config.some_map["bare"], "bare-string");
+
+    Ok(())
+}
+
+#[test]
+fn repeated_nodes() -> Result<()> {
+    let file: &str = "
+some_string \"\"\"
+    multi
+      line
+    \"\"\"
+some_bool #false
+some_list
+some_nest some_int=1 some_float=0.5
+servers host=a port=1
+servers host=b port=2
+some_map {}
+";
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .use_str(file, ConfigurationVariant::Kdl)?
+        .build()?;
+    assert_eq!(config.some_string, "multi\n  line");
+    assert!(config.some_list.is_empty());
+    assert_eq!(config.servers[0].host, "a");
+    assert_eq!(config.servers[1].port, 2);
+    assert!(config.some_map.is_empty());
+
+    let result: Result<_, Error> =
+        SomeBasicConfig::builder().use_str("some_string \"unclosed", ConfigurationVariant::Kdl);
+    assert!(matches!(result, Err(Error::KdlError(message)) if message.starts_with("line 1")));
+
+    for escape in [r"\u}", r"\u{}", r"\u0}", r"\u{110000}"] {
+        let file: String = format!("some_string \"{escape}\"");
+        let result: Result<_, Error> =
+            SomeBasicConfig::builder().use_str(&file, ConfigurationVariant::Kdl);
+        assert!(matches!(result, Err(Error::KdlError(_))));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn kdl_round_trip() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_round_trip.kdl");
+    let data: SomeBasicConfig = SomeBasicConfig {
+        some_string: "Hello, \"world\"!\n".to_string(),
+        some_bool: true,
+        some_list: vec![-1],
+        some_nest: SomeBasicNestedConfig {
+            some_int: -4,
+            some_float: 0.5,
+            some_unsigned: None,
+        },
+        servers: vec![SomeServerConfig {
+            host: "localhost".to_string(),
+            port: 8080,
+        }],
+        some_map: BTreeMap::from([("1 two".to_string(), "three".to_string())]),
+    };
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .make_override(&path, &data, None)?
+        .build()?;
+    assert_eq!(config, data);
+    assert_eq!(
+        fs::read_to_string(&path)?,
+        r#"some_string "Hello, \"world\"!\n"
+some_bool #true
+some_list {
+    - -1
+}
+some_nest {
+    some_int -4
+    some_float 0.5
+    some_unsigned #null
+}
+servers {
+    - {
+        host "localhost"
+        port 8080
+    }
+}
+some_map {
+    "1 two" "three"
+}
+"#
+    );
+    fs::remove_file(path)?;
+
+    Ok(())
+}
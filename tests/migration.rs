@@ -0,0 +1,228 @@
+use std::{env, fs};
+
+use anyhow::Result;
+#[cfg(feature = "encryption")]
+use cogwheel::encryption::Key;
+use cogwheel::{config::ConfigurationVariant, document::Document, Configuration, Error, Secret};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// Very barebones config struct.
+struct SomeBasicConfig {
+    some_string: String,
+    some_nest: SomeBasicNestedConfig,
+}
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// A very barebones nested config struct.
+struct SomeBasicNestedConfig {
+    some_int: i32,
+}
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// A config struct with a secret in it.
+struct SomeSecretConfig {
+    some_user: String,
+    some_password: Secret<String>,
+}
+
+/// Version 2 called `some_user` `some_name`.
+fn rename_user(document: &mut Document) {
+    if let Some(map) = document.as_object_mut() {
+        let name: Document = map.remove("some_name").unwrap_or_default();
+        map.insert("some_user".to_string(), name);
+    }
+}
+
+/// Version 1 had `some_number` at the top level.
+fn nest_number(document: &mut Document) {
+    if let Some(map) = document.as_object_mut() {
+        let number: Document = map.remove("some_number").unwrap_or_default();
+        map.insert(
+            "some_nest".to_string(),
+            serde_json::json!({ "some_number": number }),
+        );
+    }
+}
+
+/// Version 2 called `some_int` `some_number`.
+fn rename_number(document: &mut Document) {
+    if let Some(nest) = document
+        .get_mut("some_nest")
+        .and_then(Document::as_object_mut)
+    {
+        let number: Document = nest.remove("some_number").unwrap_or_default();
+        nest.insert("some_int".to_string(), number);
+    }
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn migrate_configuration() -> Result<()> {
+    let version_1: &str = "some_string = \"Hello, world!\"\nsome_number = -4\n";
+    let version_2: &str =
+        "version = 2\nsome_string = \"Hello, world!\"\n\n[some_nest]\nsome_number = -4\n";
+
+    for file in [version_1, version_2] {
+        let config: SomeBasicConfig = SomeBasicConfig::builder()
+            .migration(2, 3, rename_number)
+            .migration(1, 2, nest_number)
+            .use_str(file, ConfigurationVariant::Toml)?
+            .build()?;
+
+        assert_eq!(config.some_string, "Hello, world!");
+        assert_eq!(config.some_nest.some_int, -4_i32);
+    }
+
+    let result: Result<_, Error> = SomeBasicConfig::builder()
+        .migration(2, 3, rename_number)
+        .use_str(version_1, ConfigurationVariant::Toml);
+    assert!(matches!(result, Err(Error::MissingMigration(1))));
+
+    let result: Result<_, Error> = SomeBasicConfig::builder()
+        .migration(1, 2, nest_number)
+        .use_str("version = 3", ConfigurationVariant::Toml);
+    assert!(matches!(result, Err(Error::UnsupportedVersion(3))));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn write_migrated_configuration() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_migration.toml");
+    fs::write(&path, "some_string = \"Hello, world!\"\nsome_number = -4\n")?;
+
+    SomeBasicConfig::builder()
+        .migration(1, 2, nest_number)
+        .migration(2, 3, rename_number)
+        .write_migrations()
+        .use_file(&path, ConfigurationVariant::Toml)?
+        .build()?;
+
+    let file: String = fs::read_to_string(&path)?;
+    assert!(file.starts_with("version = 3\n"));
+    assert!(file.contains("some_int = -4"));
+
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "ron")]
+fn write_migrated_ron() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_migration.ron");
+    fs::write(&path, "(some_string: \"Hello, world!\", some_number: -4)")?;
+
+    let builder = SomeBasicConfig::builder()
+        .migration(1, 2, nest_number)
+        .migration(2, 3, rename_number)
+        .write_migrations()
+        .use_file(&path, ConfigurationVariant::Ron)?;
+    let data: SomeBasicConfig = builder.build()?;
+
+    // Read without the migrations, so nothing has to go through a `Document`.
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .use_file(&path, ConfigurationVariant::Ron)?
+        .build()?;
+    assert_eq!(config.some_string, "Hello, world!");
+    assert_eq!(config.some_nest.some_int, -4_i32);
+
+    SomeBasicConfig::builder()
+        .migration(1, 2, nest_number)
+        .migration(2, 3, rename_number)
+        .make_override(&path, &data, Some(ConfigurationVariant::Ron))?;
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .use_file(&path, ConfigurationVariant::Ron)?
+        .build()?;
+    assert_eq!(config.some_nest.some_int, -4_i32);
+
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn write_migrated_profiles() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_migration_profiles.toml");
+    fs::write(
+        &path,
+        "some_string = \"Hello, world!\"\nsome_number = -4\n\n[profile.prod]\nsome_string = \"Goodbye, world!\"\n",
+    )?;
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .profile("prod")
+        .migration(1, 2, nest_number)
+        .migration(2, 3, rename_number)
+        .write_migrations()
+        .use_file(&path, ConfigurationVariant::Toml)?
+        .build()?;
+    assert_eq!(config.some_string, "Goodbye, world!");
+
+    let file: String = fs::read_to_string(&path)?;
+    assert!(file.contains("some_string = \"Hello, world!\""));
+    assert!(file.contains("[profile.prod]"));
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .profile("prod")
+        .migration(1, 2, nest_number)
+        .migration(2, 3, rename_number)
+        .use_file(&path, ConfigurationVariant::Toml)?
+        .build()?;
+    assert_eq!(config.some_string, "Goodbye, world!");
+    assert_eq!(config.some_nest.some_int, -4_i32);
+
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn write_migrated_secrets() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_migration_secrets.toml");
+    fs::write(
+        &path,
+        "some_name = \"admin\"\nsome_password = \"hunter2\"\n",
+    )?;
+
+    let config: SomeSecretConfig = SomeSecretConfig::builder()
+        .migration(1, 2, rename_user)
+        .write_migrations()
+        .use_file(&path, ConfigurationVariant::Toml)?
+        .build()?;
+    assert_eq!(config.some_user, "admin");
+
+    let file: String = fs::read_to_string(&path)?;
+    assert!(file.contains("some_user = \"admin\""));
+    assert!(file.contains("some_password = \"hunter2\""));
+
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+#[cfg(all(feature = "toml", feature = "encryption"))]
+fn write_migrated_encrypted_values() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_migration_encrypted.toml");
+    let key: Key = Key::generate();
+    let password: String = key.encrypt("hunter2")?;
+    fs::write(
+        &path,
+        format!("some_name = \"admin\"\nsome_password = \"{password}\"\n"),
+    )?;
+
+    let config: SomeSecretConfig = SomeSecretConfig::builder()
+        .decrypt_with(Key::from_base64(&key.to_base64())?)
+        .migration(1, 2, rename_user)
+        .write_migrations()
+        .use_file(&path, ConfigurationVariant::Toml)?
+        .build()?;
+    assert_eq!(config.some_password.expose(), "hunter2");
+
+    let file: String = fs::read_to_string(&path)?;
+    assert!(file.contains(&password));
+    assert!(!file.contains("hunter2"));
+
+    fs::remove_file(path)?;
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use anyhow::Result;
+use cogwheel::{config::ConfigurationVariant, Configuration, Error, Secret};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// Very barebones config struct.
+struct SomeBasicConfig {
+    some_string: String,
+    some_password: Secret<String>,
+    some_list: Vec<i32>,
+    some_nest: SomeBasicNestedConfig,
+}
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// A very barebones nested config struct.
+struct SomeBasicNestedConfig {
+    some_int: i32,
+    some_unsigned: Option<u32>,
+}
+
+const FILE: &str = r#"
+some_string = "Hello, world!"
+some_password = "hunter2"
+some_list = [1, 2, 3]
+
+[some_nest]
+some_int = -4
+some_unsigned = 2147483648
+"#;
+
+#[test]
+#[cfg(feature = "toml")]
+fn apply_merge_patch() -> Result<()> {
+    let mut builder = SomeBasicConfig::builder().use_str(FILE, ConfigurationVariant::Toml)?;
+    builder.apply_merge_patch(&json!({
+        "some_string": "Goodbye, world!",
+        "some_nest": { "some_unsigned": null },
+    }))?;
+    let config: SomeBasicConfig = builder.build()?;
+
+    assert_eq!(config.some_string, "Goodbye, world!");
+    assert_eq!(config.some_password.expose(), "hunter2");
+    assert_eq!(config.some_nest.some_int, -4_i32);
+    assert_eq!(config.some_nest.some_unsigned, None);
+
+    let mut builder = SomeBasicConfig::builder().use_str(FILE, ConfigurationVariant::Toml)?;
+    let result: Result<(), Error> =
+        builder.apply_merge_patch(&json!({ "some_nest": { "some_int": "four" } }));
+    assert!(matches!(result, Err(Error::JsonError(_))));
+
+    // A rejected patch leaves the configuration as it was.
+    let config: SomeBasicConfig = builder.build()?;
+    assert_eq!(config.some_nest.some_int, -4_i32);
+    assert_eq!(config.some_password.expose(), "hunter2");
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn apply_patch() -> Result<()> {
+    let mut builder = SomeBasicConfig::builder().use_str(FILE, ConfigurationVariant::Toml)?;
+    builder.apply_patch(&json!([
+        { "op": "test", "path": "/some_nest/some_int", "value": -4 },
+        { "op": "replace", "path": "/some_nest/some_int", "value": 4 },
+        { "op": "add", "path": "/some_list/-", "value": 4 },
+        { "op": "remove", "path": "/some_list/0" },
+        { "op": "copy", "from": "/some_list/0", "path": "/some_list/0" },
+    ]))?;
+    let config: SomeBasicConfig = builder.build()?;
+
+    assert_eq!(config.some_nest.some_int, 4_i32);
+    assert_eq!(config.some_list, vec![2, 2, 3, 4]);
+
+    let mut builder = SomeBasicConfig::builder().use_str(FILE, ConfigurationVariant::Toml)?;
+    let result: Result<(), Error> = builder.apply_patch(&json!([
+        { "op": "replace", "path": "/some_string", "value": "Goodbye, world!" },
+        { "op": "test", "path": "/some_nest/some_int", "value": 4 },
+    ]));
+    assert!(matches!(result, Err(Error::InvalidPatch(_))));
+
+    // None of a rejected patch is kept.
+    let config: SomeBasicConfig = builder.build()?;
+    assert_eq!(config.some_string, "Hello, world!");
+    assert_eq!(config.some_list, vec![1, 2, 3]);
+
+    Ok(())
+}
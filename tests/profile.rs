@@ -0,0 +1,71 @@
+use anyhow::Result;
+use cogwheel::{config::ConfigurationVariant, Configuration, Error};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// Very barebones config struct.
+struct SomeBasicConfig {
+    some_string: String,
+    some_bool: bool,
+    some_nest: SomeBasicNestedConfig,
+}
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// A very barebones nested config struct.
+struct SomeBasicNestedConfig {
+    some_int: i32,
+    some_unsigned: u32,
+}
+
+const FILE: &str = r#"
+some_string = "Hello, world!"
+some_bool = true
+
+[some_nest]
+some_int = -4
+some_unsigned = 2147483648
+
+[profile.dev.some_nest]
+some_int = 4
+
+[profile.prod]
+some_string = "Goodbye, world!"
+some_bool = false
+"#;
+
+#[test]
+#[cfg(feature = "toml")]
+fn use_profile() -> Result<()> {
+    let base: SomeBasicConfig = SomeBasicConfig::builder()
+        .use_str(FILE, ConfigurationVariant::Toml)?
+        .build()?;
+    let dev: SomeBasicConfig = SomeBasicConfig::builder()
+        .profile("dev")
+        .use_str(FILE, ConfigurationVariant::Toml)?
+        .build()?;
+    let prod: SomeBasicConfig = SomeBasicConfig::builder()
+        .profile("prod")
+        .use_str(FILE, ConfigurationVariant::Toml)?
+        .build()?;
+
+    assert_eq!(base.some_nest.some_int, -4_i32);
+    assert_eq!(dev.some_string, "Hello, world!");
+    assert_eq!(dev.some_nest.some_int, 4_i32);
+    assert_eq!(dev.some_nest.some_unsigned, 2_147_483_648_u32);
+    assert_eq!(prod.some_string, "Goodbye, world!");
+    assert!(!prod.some_bool);
+    assert_eq!(prod.some_nest.some_int, -4_i32);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn use_unknown_profile() {
+    let result: Result<SomeBasicConfig, Error> = SomeBasicConfig::builder()
+        .profile("staging")
+        .use_str(FILE, ConfigurationVariant::Toml)
+        .and_then(cogwheel::config::ConfigurationBuilder::build);
+
+    assert!(matches!(result, Err(Error::UnknownProfile(profile)) if profile == "staging"));
+}
@@ -0,0 +1,120 @@
+#![cfg(feature = "properties")]
+
+use std::{env, fs};
+
+use anyhow::Result;
+use cogwheel::{config::ConfigurationVariant, properties, Configuration, Error};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// Very barebones config struct.
+struct SomeBasicConfig {
+    some_string: String,
+    some_bool: bool,
+    some_nest: SomeBasicNestedConfig,
+}
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A very barebones nested config struct.
+struct SomeBasicNestedConfig {
+    some_int: i32,
+    some_float: f32,
+    some_unsigned: Option<u32>,
+}
+
+#[test]
+fn deserialize_properties() -> Result<()> {
+    let file: &str = r"
+# Comments and blank lines are skipped.
+! So are these.
+some_string = Hello, \
+              wörld 🌍\n
+some_bool: true
+some_nest.some_int -4
+some_nest.some_float=2.5
+";
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .use_str(file, ConfigurationVariant::Properties)?
+        .build()?;
+
+    assert_eq!(config.some_string, "Hello, w\u{f6}rld \u{1f30d}\n");
+    assert!(config.some_bool);
+    assert_eq!(config.some_nest.some_int, -4_i32);
+    assert_eq!(config.some_nest.some_float, 2.5);
+    assert_eq!(config.some_nest.some_unsigned, None);
+
+    Ok(())
+}
+
+#[test]
+fn properties_round_trip() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_round_trip.properties");
+    let data: SomeBasicConfig = SomeBasicConfig {
+        some_string: " Hello, w\u{f6}rld = \u{1f30d}!".to_string(),
+        some_bool: true,
+        some_nest: SomeBasicNestedConfig {
+            some_int: -4,
+            some_float: 0.5,
+            some_unsigned: Some(2_147_483_648),
+        },
+    };
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .make_override(&path, &data, None)?
+        .build()?;
+    assert_eq!(config, data);
+    assert_eq!(
+        fs::read_to_string(&path)?,
+        "some_string=\\ Hello, w\\u00f6rld \\= \\ud83c\\udf0d\\!\nsome_bool=true\n\
+         some_nest.some_int=-4\nsome_nest.some_float=0.5\nsome_nest.some_unsigned=2147483648\n"
+    );
+    fs::remove_file(path)?;
+
+    Ok(())
+}
+
+#[test]
+fn strings_stay_strings() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_strings.properties");
+    let data: SomeBasicConfig = SomeBasicConfig {
+        some_string: "true".to_string(),
+        ..SomeBasicConfig::default()
+    };
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .make_override(&path, &data, None)?
+        .build()?;
+    assert_eq!(config, data);
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .use_file(&path, ConfigurationVariant::Properties)?
+        .build()?;
+    assert_eq!(config, data);
+    fs::remove_file(path)?;
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .lenient()
+        .use_str("some_string=123", ConfigurationVariant::Properties)?
+        .build()?;
+    assert_eq!(config.some_string, "123");
+
+    Ok(())
+}
+
+#[test]
+fn conflicting_keys() -> Result<()> {
+    for file in ["a=1\na.b=2", "a.b=2\na=1", "a.b=1\na.b.c=2"] {
+        assert!(matches!(
+            properties::from_str(file),
+            Err(Error::InvalidProperties(2))
+        ));
+    }
+
+    // Repeated keys still replace each other.
+    assert_eq!(
+        properties::from_str("a.b=1\na.b=2")?,
+        serde_json::json!({ "a": { "b": "2" } })
+    );
+
+    Ok(())
+}
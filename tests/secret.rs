@@ -0,0 +1,80 @@
+use std::{env, fs};
+
+use anyhow::Result;
+use cogwheel::{config::ConfigurationVariant, Configuration, Secret};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// A config struct with a secret in it.
+struct SomeSecretConfig {
+    some_user: String,
+    some_password: Secret<String>,
+}
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// A config struct with a secret that isn't a string in it.
+struct SomePinConfig {
+    some_pin: Secret<u32>,
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn secret_is_redacted() -> Result<()> {
+    let file: &str = r#"
+    some_user = "admin"
+    some_password = "hunter2"
+    "#;
+
+    let config: SomeSecretConfig = SomeSecretConfig::builder()
+        .use_str(file, ConfigurationVariant::Toml)?
+        .build()?;
+
+    assert_eq!(config.some_password.expose(), "hunter2");
+    assert_eq!(config.some_password.to_string(), "[REDACTED]");
+    assert!(!format!("{config:?}").contains("hunter2"));
+    assert!(!toml::to_string(&config)?.contains("hunter2"));
+    assert!(cogwheel::secret::exposed(|| toml::to_string(&config))?.contains("hunter2"));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn secret_is_exposed_when_making() -> Result<()> {
+    let data: SomeSecretConfig = SomeSecretConfig {
+        some_user: "admin".to_string(),
+        some_password: Secret::new("hunter2".to_string()),
+    };
+    let redacted_path = env::temp_dir().join("cogwheel_secret_redacted.toml");
+    let exposed_path = env::temp_dir().join("cogwheel_secret_exposed.toml");
+
+    let redacted: SomeSecretConfig = SomeSecretConfig::builder()
+        .make_override(&redacted_path, &data, None)?
+        .build()?;
+    let exposed: SomeSecretConfig = SomeSecretConfig::builder()
+        .expose_secrets()
+        .make_override(&exposed_path, &data, None)?
+        .build()?;
+
+    assert_eq!(redacted.some_password.expose(), "hunter2");
+    assert_eq!(exposed.some_password.expose(), "hunter2");
+    assert!(!fs::read_to_string(&redacted_path)?.contains("hunter2"));
+    assert!(fs::read_to_string(&exposed_path)?.contains("hunter2"));
+
+    let path = env::temp_dir().join("cogwheel_secret_pin.toml");
+    let config: SomePinConfig = SomePinConfig::builder()
+        .make_override(
+            &path,
+            &SomePinConfig {
+                some_pin: Secret::new(1234),
+            },
+            None,
+        )?
+        .build()?;
+    assert_eq!(*config.some_pin.expose(), 1234);
+
+    fs::remove_file(redacted_path)?;
+    fs::remove_file(exposed_path)?;
+    fs::remove_file(path)?;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+#![cfg(feature = "toml")]
+
+use std::{env, fs};
+
+use anyhow::Result;
+use cogwheel::{config::ConfigurationVariant, Configuration, Error, SharedFile};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A subsystem's config struct.
+struct SomeServerConfig {
+    host: String,
+    #[cogwheel(default = "8080")]
+    port: u16,
+}
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// Another subsystem's config struct.
+struct SomeDatabaseConfig {
+    url: String,
+    pool_size: u32,
+}
+
+const FILE: &str = r#"
+[server]
+host = "localhost"
+
+[database]
+url = "postgres://localhost/app"
+"#;
+
+#[test]
+fn read_sections() -> Result<()> {
+    let file: SharedFile = SharedFile::parse(FILE, ConfigurationVariant::Toml)?;
+
+    let server: SomeServerConfig = file.section("server")?;
+    assert_eq!(server.host, "localhost");
+    assert_eq!(server.port, 8080);
+
+    let result: Result<SomeDatabaseConfig, Error> = file.section("database");
+    assert!(result.is_err());
+    let database: SomeDatabaseConfig = SomeDatabaseConfig::builder()
+        .lenient()
+        .use_section(&file, "database")?
+        .build()?;
+    assert_eq!(database.url, "postgres://localhost/app");
+    assert_eq!(database.pool_size, 0);
+
+    let result: Result<SomeServerConfig, Error> = file.section("cache");
+    assert!(matches!(result, Err(Error::MissingPath(path)) if path == "cache"));
+
+    Ok(())
+}
+
+#[test]
+fn save_section() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_shared.toml");
+    fs::write(&path, FILE)?;
+    let file: SharedFile = SharedFile::open(&path, ConfigurationVariant::Toml)?;
+
+    let data: SomeServerConfig = SomeServerConfig {
+        host: "example.com".to_string(),
+        port: 443,
+    };
+    let server: SomeServerConfig = SomeServerConfig::builder()
+        .use_section(&file, "server")?
+        .save(&data)?
+        .build()?;
+    assert_eq!(server, data);
+
+    let file: SharedFile = SharedFile::open(&path, ConfigurationVariant::Toml)?;
+    let database: SomeDatabaseConfig = SomeDatabaseConfig::builder()
+        .lenient()
+        .use_section(&file, "database")?
+        .build()?;
+    assert_eq!(database.url, "postgres://localhost/app");
+    fs::remove_file(path)?;
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use std::{collections::BTreeMap, env};
+
+use anyhow::Result;
+use cogwheel::{
+    config::ConfigurationVariant,
+    document::{self, Document, Map},
+    source::{EnvSource, StrSource},
+    Configuration, Error, Source,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// Very barebones config struct.
+struct SomeBasicConfig {
+    some_string: String,
+    some_bool: bool,
+    some_nest: SomeBasicNestedConfig,
+}
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// A very barebones nested config struct.
+struct SomeBasicNestedConfig {
+    some_int: i32,
+    some_unsigned: Option<u32>,
+}
+
+/// A stand-in for a key-value store, with dotted keys.
+struct KeyValueStore(BTreeMap<&'static str, Document>);
+
+impl Source for KeyValueStore {
+    fn label(&self) -> String {
+        "the key-value store".to_string()
+    }
+
+    fn load(&self) -> Result<Document, Error> {
+        let mut document: Document = Document::Object(Map::new());
+        for (key, value) in &self.0 {
+            document::insert_path(&mut document, key, value.clone());
+        }
+        Ok(document)
+    }
+}
+
+/// A source that's never reachable.
+struct Unreachable;
+
+impl Source for Unreachable {
+    fn label(&self) -> String {
+        "an unreachable store".to_string()
+    }
+
+    fn load(&self) -> Result<Document, Error> {
+        Err(Error::FileNotExists)
+    }
+}
+
+const FILE: &str = r#"
+some_string = "Hello, world!"
+some_bool = true
+
+[some_nest]
+some_int = -4
+"#;
+
+#[test]
+#[cfg(feature = "toml")]
+fn layered_sources() -> Result<()> {
+    env::set_var("COGWHEEL_SOURCE_SOME_NEST__SOME_UNSIGNED", "2147483648");
+
+    let builder = SomeBasicConfig::builder()
+        .add_source(StrSource::new(FILE, ConfigurationVariant::Toml))?
+        .add_source(KeyValueStore(BTreeMap::from([
+            ("some_bool", false.into()),
+            ("some_nest.some_int", 4.into()),
+        ])))?
+        .add_source(EnvSource::new("COGWHEEL_SOURCE"))?;
+
+    assert_eq!(builder.source_of("some_string"), Some("a TOML string"));
+    assert_eq!(
+        builder.source_of("some_nest.some_int"),
+        Some("the key-value store")
+    );
+    assert_eq!(
+        builder.source_of("some_nest.some_unsigned"),
+        Some("environment variables starting with `COGWHEEL_SOURCE_`")
+    );
+    assert_eq!(builder.source_of("some_missing"), None);
+
+    let config: SomeBasicConfig = builder.build()?;
+    assert_eq!(config.some_string, "Hello, world!");
+    assert!(!config.some_bool);
+    assert_eq!(config.some_nest.some_int, 4_i32);
+    assert_eq!(config.some_nest.some_unsigned, Some(2_147_483_648_u32));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn env_values_are_read_as_their_field() -> Result<()> {
+    env::set_var("COGWHEEL_STRINGS_SOME_STRING", "123");
+    env::set_var("COGWHEEL_STRINGS_SOME_BOOL", "true");
+    env::set_var("COGWHEEL_STRINGS_SOME_NEST__SOME_INT", "-4");
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .add_source(EnvSource::new("COGWHEEL_STRINGS"))?
+        .build()?;
+    assert_eq!(config.some_string, "123");
+    assert!(config.some_bool);
+    assert_eq!(config.some_nest.some_int, -4_i32);
+    assert_eq!(config.some_nest.some_unsigned, None);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn strings_are_only_read_as_values_from_string_sources() {
+    let file: &str = "some_string = \"a\"\nsome_bool = \"true\"\n[some_nest]\nsome_int = \"4\"";
+
+    // Whether a file parses doesn't depend on it going through a `Document`.
+    assert!(SomeBasicConfig::builder()
+        .use_str(file, ConfigurationVariant::Toml)
+        .is_err());
+    assert!(SomeBasicConfig::builder()
+        .lenient()
+        .use_str(file, ConfigurationVariant::Toml)
+        .is_err());
+    assert!(SomeBasicConfig::builder()
+        .add_source(StrSource::new(file, ConfigurationVariant::Toml))
+        .is_err());
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn failing_source() -> Result<()> {
+    let result: Result<_, Error> = SomeBasicConfig::builder()
+        .add_source(StrSource::new(FILE, ConfigurationVariant::Toml))?
+        .add_source(Unreachable);
+
+    match result {
+        Err(Error::SourceError(label, _)) => assert_eq!(label, "an unreachable store"),
+        _ => panic!("expected a source error"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "toml")]
+
+use std::{env, fs};
+
+use anyhow::Result;
+use cogwheel::{config::ConfigurationVariant, Configuration, Error};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// Very barebones config struct.
+struct SomeBasicConfig {
+    some_string: String,
+    some_bool: bool,
+}
+
+const FILE: &str = r#"
+[package]
+name = "some_crate"
+
+[package.metadata.some_tool]
+some_string = "Hello, world!"
+some_bool = true
+"#;
+
+#[test]
+fn use_str_at() -> Result<()> {
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .use_str_at(
+            FILE,
+            "package.metadata.some_tool",
+            ConfigurationVariant::Toml,
+        )?
+        .build()?;
+    assert_eq!(config.some_string, "Hello, world!");
+    assert!(config.some_bool);
+
+    let result: Result<_, Error> = SomeBasicConfig::builder().use_str_at(
+        FILE,
+        "package.metadata.other_tool",
+        ConfigurationVariant::Toml,
+    );
+    assert!(
+        matches!(result, Err(Error::MissingPath(path)) if path == "package.metadata.other_tool")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn use_file_at_keeps_the_rest_of_the_file() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_subtree.toml");
+    fs::write(&path, FILE)?;
+
+    let builder = SomeBasicConfig::builder().use_file_at(
+        &path,
+        "package.metadata.some_tool",
+        ConfigurationVariant::Toml,
+    )?;
+    let data: SomeBasicConfig = SomeBasicConfig {
+        some_string: "Goodbye, world!".to_string(),
+        some_bool: false,
+    };
+    let config: SomeBasicConfig = builder.save(&data)?.reload()?.build()?;
+    assert_eq!(config, data);
+
+    let output: toml::Table = toml::from_str(&fs::read_to_string(&path)?)?;
+    assert_eq!(output["package"]["name"].as_str(), Some("some_crate"));
+    assert_eq!(
+        output["package"]["metadata"]["some_tool"]["some_string"].as_str(),
+        Some("Goodbye, world!")
+    );
+    fs::remove_file(path)?;
+
+    Ok(())
+}
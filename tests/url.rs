@@ -0,0 +1,105 @@
+#![cfg(all(feature = "url", feature = "toml"))]
+
+use std::{
+    env, fs,
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+};
+
+use anyhow::Result;
+use cogwheel::{Configuration, Warning};
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Response, Server};
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// Very barebones config struct.
+struct SomeBasicConfig {
+    some_string: String,
+    some_nest: SomeBasicNestedConfig,
+}
+
+#[derive(Debug, Default, Configuration, Serialize, Deserialize)]
+/// A very barebones nested config struct.
+struct SomeBasicNestedConfig {
+    some_int: i32,
+}
+
+const FILE: &str = r#"
+some_string = "Hello, world!"
+
+[some_nest]
+some_int = -4
+"#;
+
+/// Serves `FILE` as TOML with an `ETag` twice, answering `304` if the client already has it.
+fn serve() -> Result<(String, JoinHandle<()>)> {
+    let server: Server = Server::http("127.0.0.1:0").map_err(|error| anyhow::anyhow!(error))?;
+    let url: String = format!("http://{}/config", server.server_addr());
+
+    let server = thread::spawn(move || {
+        for request in server.incoming_requests().take(2) {
+            let cached: bool = request.headers().iter().any(|header: &Header| {
+                header.field.equiv("If-None-Match") && header.value.as_str() == "\"v1\""
+            });
+            let response = if cached {
+                Response::from_string("").with_status_code(304)
+            } else {
+                Response::from_string(FILE)
+                    .with_header(Header::from_bytes("Content-Type", "application/toml").unwrap())
+                    .with_header(Header::from_bytes("ETag", "\"v1\"").unwrap())
+            };
+            request.respond(response).unwrap();
+        }
+    });
+
+    Ok((url, server))
+}
+
+#[test]
+fn use_url() -> Result<()> {
+    let (url, server): (String, JoinHandle<()>) = serve()?;
+    let cache = env::temp_dir().join("cogwheel_url_cache.toml");
+
+    let builder = SomeBasicConfig::builder()
+        .cache_url_at(&cache)
+        .use_url(&url)?;
+    assert_eq!(builder.build_ref()?.some_string, "Hello, world!");
+    assert_eq!(fs::read_to_string(&cache)?, FILE);
+
+    // The server answers `304`, so nothing changes.
+    let builder = builder.reload()?;
+    assert_eq!(builder.build_ref()?.some_nest.some_int, -4_i32);
+
+    // The server is gone now, so the cached copy is used.
+    server.join().unwrap();
+    let warnings: Arc<Mutex<Vec<Warning>>> = Arc::default();
+    let sink: Arc<Mutex<Vec<Warning>>> = Arc::clone(&warnings);
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .cache_url_at(&cache)
+        .on_warning(move |warning: &Warning| sink.lock().unwrap().push(warning.clone()))
+        .use_url(&url)?
+        .build()?;
+    assert_eq!(config.some_string, "Hello, world!");
+    assert!(matches!(
+        warnings.lock().unwrap().as_slice(),
+        [Warning::UnreachableUrl { .. }]
+    ));
+
+    fs::remove_file(&cache)?;
+    assert!(SomeBasicConfig::builder().use_url(&url).is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "tokio")]
+async fn reload_url_async() -> Result<()> {
+    let (url, server): (String, JoinHandle<()>) = serve()?;
+
+    let builder = SomeBasicConfig::builder().use_url(&url)?;
+    let builder = builder.reload_async().await?;
+    assert_eq!(builder.build_ref()?.some_nest.some_int, -4_i32);
+    server.join().unwrap();
+
+    Ok(())
+}
@@ -0,0 +1,225 @@
+#![cfg(feature = "xml")]
+
+use std::{collections::BTreeMap, env, fs};
+
+use anyhow::Result;
+use cogwheel::{config::ConfigurationVariant, Configuration, Error};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// Very barebones config struct.
+struct SomeBasicConfig {
+    some_string: String,
+    some_bool: bool,
+    some_list: Vec<i64>,
+    some_nest: SomeBasicNestedConfig,
+    server: Vec<SomeServerConfig>,
+}
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A very barebones nested config struct.
+struct SomeBasicNestedConfig {
+    some_int: i32,
+    some_float: f32,
+    some_unsigned: Option<u32>,
+}
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A config struct that's repeated.
+struct SomeServerConfig {
+    host: String,
+    port: u16,
+    #[serde(rename = "$text")]
+    description: String,
+}
+
+#[test]
+fn deserialize_xml() -> Result<()> {
+    let file: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- Comments are skipped. -->
+<vendor:settings xmlns:vendor="https://example.com">
+    <some_string>Hello, &lt;world&gt; &#x1F30D;<![CDATA[ & more]]></some_string>
+    <some_bool>true</some_bool>
+    <some_list>
+        <item>1</item>
+        <item>-2</item>
+    </some_list>
+    <some_nest some_int="-4">
+        <some_float>2.5</some_float>
+    </some_nest>
+    <server host="localhost" port="8080">The primary server</server>
+    <server host="example.com" port="443">The backup server</server>
+</vendor:settings>
+"#;
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .use_str(file, ConfigurationVariant::Xml)?
+        .build()?;
+
+    assert_eq!(config.some_string, "Hello, <world> \u{1f30d} & more");
+    assert!(config.some_bool);
+    assert_eq!(config.some_list, [1, -2]);
+    assert_eq!(config.some_nest.some_int, -4_i32);
+    assert_eq!(config.some_nest.some_float, 2.5);
+    assert_eq!(config.some_nest.some_unsigned, None);
+    assert_eq!(config.server.len(), 2);
+    assert_eq!(config.server[1].port, 443);
+    assert_eq!(config.server[1].description, "The backup server");
+
+    let result: Result<_, Error> =
+        SomeBasicConfig::builder().use_str("<a><b></a>", ConfigurationVariant::Xml);
+    assert!(matches!(result, Err(Error::XmlError(_))));
+
+    Ok(())
+}
+
+#[test]
+fn xml_round_trip() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_round_trip.xml");
+    let data: SomeBasicConfig = SomeBasicConfig {
+        some_string: "Hello, <\"world\"> & all!".to_string(),
+        some_bool: true,
+        some_list: vec![-1],
+        some_nest: SomeBasicNestedConfig {
+            some_int: -4,
+            some_float: 0.5,
+            some_unsigned: None,
+        },
+        server: vec![SomeServerConfig {
+            host: "localhost".to_string(),
+            port: 8080,
+            description: "The only server".to_string(),
+        }],
+    };
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .make_override(&path, &data, None)?
+        .build()?;
+    assert_eq!(config, data);
+    assert_eq!(
+        fs::read_to_string(&path)?,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<configuration>
+    <some_string>Hello, &lt;&quot;world&quot;&gt; &amp; all!</some_string>
+    <some_bool>true</some_bool>
+    <some_list>
+        <item>-1</item>
+    </some_list>
+    <some_nest>
+        <some_int>-4</some_int>
+        <some_float>0.5</some_float>
+    </some_nest>
+    <server>
+        <item>
+            <host>localhost</host>
+            <port>8080</port>
+            The only server
+        </item>
+    </server>
+</configuration>
+"#
+    );
+    fs::remove_file(path)?;
+
+    Ok(())
+}
+
+#[test]
+fn strings_stay_strings() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_strings.xml");
+    let data: SomeBasicConfig = SomeBasicConfig {
+        some_string: "true".to_string(),
+        some_list: vec![1],
+        server: vec![SomeServerConfig {
+            host: "123".to_string(),
+            port: 80,
+            description: "false".to_string(),
+        }],
+        ..SomeBasicConfig::default()
+    };
+
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .make_override(&path, &data, None)?
+        .build()?;
+    assert_eq!(config, data);
+    fs::remove_file(path)?;
+
+    let file: &str = r#"<settings>
+    <some_string>123</some_string>
+    <server>
+        <item host="4" port="80">5</item>
+    </server>
+</settings>"#;
+    let config: SomeBasicConfig = SomeBasicConfig::builder()
+        .lenient()
+        .use_str(file, ConfigurationVariant::Xml)?
+        .build()?;
+    assert_eq!(config.some_string, "123");
+    assert_eq!(config.server[0].host, "4");
+    assert_eq!(config.server[0].port, 80);
+    assert_eq!(config.server[0].description, "5");
+
+    Ok(())
+}
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A config struct with things that can be empty.
+struct SomeEmptyConfig {
+    some_map: BTreeMap<String, i64>,
+    some_list: Vec<i64>,
+    some_string: String,
+    some_nest: SomeEmptyNestedConfig,
+}
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A nested config struct with nothing in it.
+struct SomeEmptyNestedConfig {}
+
+#[test]
+fn empty_round_trip() -> Result<()> {
+    let path = env::temp_dir().join("cogwheel_empty.xml");
+    let data: SomeEmptyConfig = SomeEmptyConfig::default();
+
+    let config: SomeEmptyConfig = SomeEmptyConfig::builder()
+        .make_override(&path, &data, None)?
+        .build()?;
+    assert_eq!(config, data);
+    assert_eq!(
+        fs::read_to_string(&path)?,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<configuration>
+    <some_map/>
+    <some_list/>
+    <some_string></some_string>
+    <some_nest/>
+</configuration>
+"#
+    );
+    fs::remove_file(&path)?;
+
+    let file: &str =
+        "<configuration>\n    <some_map>\n    </some_map>\n    <some_list></some_list>\n    \
+                      <some_string/>\n    <some_nest>\n    </some_nest>\n</configuration>";
+    let config: SomeEmptyConfig = SomeEmptyConfig::builder()
+        .use_str(file, ConfigurationVariant::Xml)?
+        .build()?;
+    assert_eq!(config, data);
+
+    let result: Result<_, Error> = SomeListConfig::builder().make_override(
+        &path,
+        &SomeListConfig {
+            some_list: vec![Some(1), None, Some(3)],
+        },
+        None,
+    );
+    assert!(matches!(result, Err(Error::XmlError(_))));
+    assert!(!path.exists());
+
+    Ok(())
+}
+
+#[derive(Debug, Default, PartialEq, Configuration, Serialize, Deserialize)]
+/// A config struct with a list that can have `null`s in it.
+struct SomeListConfig {
+    some_list: Vec<Option<i64>>,
+}